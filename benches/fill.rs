@@ -0,0 +1,56 @@
+extern crate criterion;
+extern crate geo;
+extern crate narsil;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use geo::prelude::BoundingRect;
+use narsil::config::Config;
+use narsil::pattern::*;
+use std::fs::File;
+
+const LAYERS: usize = 10;
+
+// A 200mm plate with a few 10mm parts scattered across it, in the default
+// 0.1 micron resolution
+fn sparse_plate() -> MultiPolygon {
+    let part = 100_000_000;
+    [(0, 0), (1_700_000_000, 200_000_000), (400_000_000, 1_800_000_000), (1_900_000_000, 1_900_000_000)]
+        .iter()
+        .map(|&(x, y)| {
+            Rect::new(Coordinate { x, y }, Coordinate { x: x + part, y: y + part }).to_polygon()
+        })
+        .collect()
+}
+
+fn fill_benchmark(c: &mut Criterion) {
+    let config = Config::new(File::open("conf/default.yaml").unwrap()).unwrap();
+    let plate = sparse_plate();
+    let bounds = plate.bounding_rect().unwrap();
+    let layers = vec![plate.clone(); LAYERS];
+    let layer_z: Vec<f64> = (0..LAYERS).map(|l| l as f64 * config.layer_height).collect();
+
+    c.bench_function("linear fill whole layer", |b| {
+        b.iter(|| {
+            layers
+                .iter()
+                .zip(layer_z.iter())
+                .enumerate()
+                .map(|(layer, (region, z))| {
+                    LinearPattern.fill(&FillContext {
+                        layer,
+                        z: *z,
+                        region,
+                        bounds,
+                        config: &config,
+                    })
+                })
+                .collect::<Vec<MultiLineString>>()
+        })
+    });
+    c.bench_function("linear fill per region", |b| {
+        b.iter(|| fill_regions(&LinearPattern, &layers, &layer_z, bounds, &config))
+    });
+}
+
+criterion_group!(benches, fill_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,169 @@
+use geo::prelude::*;
+use geo_clipper::*;
+use rayon::prelude::*;
+
+use crate::config::*;
+use crate::geometry::polygon_utils::*;
+use crate::infill::*;
+use crate::types::*;
+
+pub struct Bridge {
+    // Bridge span plus the anchors it rests on
+    pub area: MultiPolygon,
+    // Direction the bridge lines run, in degrees
+    pub angle: f64,
+}
+
+// Direction across the narrowest width of the span, in whole degrees
+fn narrowest_angle(span: &Polygon) -> f64 {
+    let extent = |angle: f64| {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let (min, max) = span.exterior().0.iter().fold((f64::MAX, f64::MIN), |(min, max), c| {
+            let along = c.x as f64 * cos + c.y as f64 * sin;
+            (min.min(along), max.max(along))
+        });
+        max - min
+    };
+    (0..180)
+        .map(|degrees| degrees as f64)
+        .min_by(|a, b| extent(*a).partial_cmp(&extent(*b)).unwrap())
+        .unwrap()
+}
+
+// A bottom exposed region is a bridge when growing it by the anchor distance
+// lands on the layer below in at least two separate places, or in one place
+// that surrounds it. Lines run between the centers of the two largest
+// supports, or across the narrowest width of a surrounded span.
+fn detect_bridge(
+    exposed: &Polygon,
+    below: &MultiPolygon,
+    interior: &MultiPolygon,
+    anchor: f64,
+) -> Option<Bridge> {
+    let grown: MultiPolygon = vec![exposed.clone()].into();
+    let grown = grown.offset(anchor, JoinType::Miter(3.0), EndType::ClosedPolygon);
+
+    let mut supports: Vec<Polygon> = grown.intersection(below).0;
+    let angle = match supports.len() {
+        0 => return None,
+        1 => {
+            let filled: MultiPolygon =
+                vec![Polygon::new(supports[0].exterior().clone(), Vec::new())].into();
+            let span: MultiPolygon = vec![exposed.clone()].into();
+            if span.difference(&filled).area_f64() > span.area_f64() * 0.01 {
+                return None;
+            }
+            narrowest_angle(exposed)
+        }
+        _ => {
+            supports.sort_by(|a, b| b.area_f64().partial_cmp(&a.area_f64()).unwrap());
+            let a = centroid_f64(&supports[0]);
+            let b = centroid_f64(&supports[1]);
+            (b.1 - a.1).atan2(b.0 - a.0).to_degrees()
+        }
+    };
+
+    let area = grown.intersection(interior);
+    if area.0.is_empty() {
+        return None;
+    }
+    Some(Bridge { area, angle })
+}
+
+pub fn detect_bridges(
+    config: &Config,
+    outlines: &Vec<MultiPolygon>,
+    bottom_exposed: &Vec<MultiPolygon>,
+    interiors: &Vec<InteriorRegions>,
+) -> Vec<Vec<Bridge>> {
+    let anchor = config.bridge_anchor_distance_dsc() as f64;
+
+    (0..outlines.len())
+        .into_par_iter()
+        .map(|i| {
+            if i == 0 {
+                return Vec::new();
+            }
+            let interior: MultiPolygon = interiors[i].0.iter().map(|r| r.poly.clone()).collect();
+            bottom_exposed[i]
+                .0
+                .iter()
+                .filter_map(|exposed| detect_bridge(exposed, &outlines[i - 1], &interior, anchor))
+                .collect()
+        })
+        .collect()
+}
+
+pub fn bridge_area(bridges: &Vec<Bridge>) -> MultiPolygon {
+    bridges
+        .iter()
+        .map(|b| b.area.0.iter().cloned())
+        .flatten()
+        .collect::<MultiPolygon>()
+        .union(&MultiPolygon::default())
+}
+
+pub fn bridge_fill(config: &Config, bridges: &Vec<Bridge>) -> MultiLineString {
+    let spacing = config.bridge_line_spacing_dsc();
+    let mut lines = MultiLineString::default();
+    for bridge in bridges.iter() {
+        if let Some(bounds) = bridge.area.bounding_rect() {
+            lines.0.extend(
+                anchored_lines_in_rect(spacing, 0.0, &[bridge.angle - 90.0], &bounds)
+                    .intersection(&bridge.area)
+                    .0
+                    .into_iter(),
+            );
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counter clockwise rectangle in millimeters
+    fn rect(x0: i64, y0: i64, x1: i64, y1: i64) -> Polygon {
+        let mm = 10_000_000;
+        let (x0, y0, x1, y1) = (x0 * mm, y0 * mm, x1 * mm, y1 * mm);
+        Polygon::new(vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1), (x0, y0)].into(), Vec::new())
+    }
+
+    fn detect(exposed: &Polygon, below: MultiPolygon) -> Option<Bridge> {
+        let config = Config::default_test();
+        let interior: MultiPolygon = vec![rect(-10, -10, 50, 50)].into();
+        detect_bridge(exposed, &below, &interior, config.bridge_anchor_distance_dsc() as f64)
+    }
+
+    #[test]
+    fn two_supports_bridge_between_them() {
+        let below: MultiPolygon = vec![rect(0, 0, 10, 10), rect(30, 0, 40, 10)].into();
+        let bridge = detect(&rect(10, 0, 30, 10), below).unwrap();
+        assert!(bridge.angle.abs() < 1.0 || (bridge.angle.abs() - 180.0).abs() < 1.0);
+
+        let lines = bridge_fill(&Config::default_test(), &vec![bridge]);
+        assert!(!lines.0.is_empty());
+        for line in lines.0.iter() {
+            let (a, b) = (line.0[0], *line.0.last().unwrap());
+            assert!((a.y - b.y).abs() < 10);
+            assert!((a.x - b.x).abs() >= 200_000_000);
+        }
+    }
+
+    #[test]
+    fn one_support_is_not_a_bridge() {
+        let below: MultiPolygon = vec![rect(0, 0, 10, 10)].into();
+        assert!(detect(&rect(10, 0, 30, 10), below).is_none());
+    }
+
+    #[test]
+    fn enclosed_span_bridges_its_narrowest_width() {
+        let mut hole = rect(10, 10, 30, 20).exterior().clone();
+        hole.0.reverse();
+        let ring = Polygon::new(rect(0, 0, 40, 30).exterior().clone(), vec![hole]);
+        let below: MultiPolygon = vec![ring].into();
+        let bridge = detect(&rect(10, 10, 30, 20), below).unwrap();
+        assert_eq!(bridge.angle, 90.0);
+    }
+}
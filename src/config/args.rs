@@ -5,7 +5,7 @@ use std::path::Path as filePath;
 
 pub struct Args {
     config_filename: String,
-    input_filename: String,
+    input_filenames: Vec<String>,
     output_filename: String,
 }
 
@@ -13,14 +13,14 @@ impl Args {
     pub fn new(cmdline: &Vec<String>) -> Result<Args, String> {
         if cmdline.len() < 4 {
             Err(format!(
-                "Usage: {} <config_file> <input_file> <output_file>",
+                "Usage: {} <config_file> <input_file>...|<plate_file> <output_file>",
                 cmdline[0]
             ))
         } else {
             Ok(Args {
                 config_filename: cmdline[1].clone(),
-                input_filename: cmdline[2].clone(),
-                output_filename: cmdline[3].clone(),
+                input_filenames: cmdline[2..cmdline.len() - 1].to_vec(),
+                output_filename: cmdline[cmdline.len() - 1].clone(),
             })
         }
     }
@@ -33,8 +33,40 @@ impl Args {
         Config::new(self.config_fh()?)
     }
 
-    pub fn input_fh(&self) -> NarsilResult<File> {
-        Ok(File::open(self.input_filename.clone())?)
+    pub fn input_filenames(&self) -> &Vec<String> {
+        &self.input_filenames
+    }
+
+    fn is_plate_file(filename: &String) -> bool {
+        match filePath::new(filename.as_str()).extension() {
+            Some(ext) => ext == "yaml" || ext == "yml",
+            None => false,
+        }
+    }
+
+    // A single .yaml/.yml input is a plate description, anything else is a
+    // list of model files that each become one object with default settings.
+    // Relative model paths in a plate are resolved against its directory.
+    pub fn plate(&self) -> NarsilResult<PlateDescription> {
+        if self.input_filenames.len() == 1 && Self::is_plate_file(&self.input_filenames[0]) {
+            let plate_filename = &self.input_filenames[0];
+            let mut plate = PlateDescription::new(File::open(plate_filename.clone())?)?;
+            let base = filePath::new(plate_filename.as_str())
+                .parent()
+                .unwrap_or(filePath::new(""));
+            for object in plate.objects.iter_mut() {
+                object.file = base.join(&object.file).to_str().unwrap().to_string();
+            }
+            Ok(plate)
+        } else {
+            Ok(PlateDescription {
+                objects: self
+                    .input_filenames
+                    .iter()
+                    .map(|filename| ObjectDescription::new(filename.clone()))
+                    .collect(),
+            })
+        }
     }
 
     pub fn output_fh(&self) -> NarsilResult<File> {
@@ -42,7 +74,37 @@ impl Args {
     }
 
     pub fn name(&self) -> String {
-        let path = filePath::new(self.input_filename.as_str());
+        let path = filePath::new(self.input_filenames[0].as_str());
         path.file_name().unwrap().to_str().unwrap().to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(inputs: &[&str]) -> Args {
+        let cmdline: Vec<String> = ["narsil", "conf/default.yaml"]
+            .iter()
+            .chain(inputs.iter())
+            .chain(["out.gcode"].iter())
+            .map(|s| s.to_string())
+            .collect();
+        Args::new(&cmdline).unwrap()
+    }
+
+    #[test]
+    fn plate_paths_are_relative_to_the_plate() {
+        let plate = args(&["conf/plate_example.yaml"]).plate().unwrap();
+        let files: Vec<&str> = plate.objects.iter().map(|o| o.file.as_str()).collect();
+        assert_eq!(files, vec!["conf/bracket.stl", "conf/cover.stl"]);
+    }
+
+    #[test]
+    fn model_files_become_plain_objects() {
+        let plate = args(&["a.stl", "b.stl"]).plate().unwrap();
+        assert_eq!(plate.objects.len(), 2);
+        assert_eq!(plate.objects[1].file, "b.stl");
+        assert_eq!(plate.objects[1].instances, 1);
+    }
+}
@@ -1,31 +1,153 @@
 use crate::error::*;
+use crate::config::{Filament, Printer};
+use crate::types::{
+    FillPattern, PathSettings, SolidFillPattern, TopFillPattern, WallGenerator, WallOrder,
+};
 use serde::{Deserialize, Serialize};
 use serde_yaml;
 use std::fs::File;
 use std::io::BufReader;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Config {
     pub layer_height: f64,
     pub resolution: f64,
     pub simplify_factor: f64,
     pub num_shells: u64,
+    #[serde(default)]
+    pub elephant_foot_compensation: f64,
+    #[serde(default)]
+    pub xy_size_compensation: f64,
+    #[serde(default)]
+    pub hole_compensation: f64,
+    #[serde(default)]
+    pub thin_walls: bool,
+    #[serde(default)]
+    pub thin_wall_variable_width: bool,
+    #[serde(default)]
+    pub wall_generator: WallGenerator,
+    #[serde(default)]
+    pub wall_order: WallOrder,
+    #[serde(default)]
+    pub spiral_vase: bool,
+    #[serde(default)]
+    pub fuzzy_skin: bool,
+    #[serde(default = "default_fuzzy_skin_thickness")]
+    pub fuzzy_skin_thickness: f64,
+    #[serde(default = "default_fuzzy_skin_point_distance")]
+    pub fuzzy_skin_point_distance: f64,
+    #[serde(default)]
+    pub fuzzy_skin_seed: u64,
+    #[serde(default)]
+    pub fuzzy_skin_skip_holes: bool,
+    #[serde(default)]
+    pub holes_first: bool,
+    #[serde(default)]
+    pub infill_first: bool,
+    #[serde(default)]
+    pub gap_fill: bool,
+    #[serde(default = "default_gap_fill_min_width")]
+    pub gap_fill_min_width: f64,
     pub nozzle_diameter: f64,
     pub shell_overlap: f64,
     pub shell_infill_overlap: f64,
     pub top_thickness: f64,
     pub bottom_thickness: f64,
+    #[serde(default)]
+    pub min_skin_area: f64,
+    #[serde(default)]
+    pub skin_expansion_distance: f64,
+    #[serde(default)]
+    pub solid_infill_below_area: f64,
     pub solid_fill_overlap_ratio: f64,
     pub solid_fill_initial_angle: f64,
     pub solid_fill_angle_increment: f64,
+    #[serde(default)]
+    pub solid_fill_pattern: SolidFillPattern,
+    #[serde(default)]
+    pub solid_fill_connect: bool,
+    #[serde(default)]
+    pub top_fill_monotonic: bool,
+    #[serde(default)]
+    pub top_fill_pattern: TopFillPattern,
+    #[serde(default = "default_bridge_speed")]
+    pub bridge_speed: f64,
+    #[serde(default = "default_bridge_flow_ratio")]
+    pub bridge_flow_ratio: f64,
+    #[serde(default = "default_bridge_fan_speed")]
+    pub bridge_fan_speed: f64,
+    #[serde(default = "default_bridge_anchor_distance")]
+    pub bridge_anchor_distance: f64,
     pub sparse_fill_density: f64,
     pub sparse_fill_initial_angle: f64,
     pub sparse_fill_angle_increment: f64,
+    #[serde(default)]
+    pub sparse_fill_pattern: FillPattern,
+    #[serde(default)]
+    pub custom_fill_pattern: Option<String>,
+    #[serde(default)]
+    pub honeycomb_cell_size: Option<f64>,
+    #[serde(default = "default_lightning_angle")]
+    pub lightning_angle: f64,
+    #[serde(default)]
+    pub auto_orient: bool,
+    #[serde(default = "default_overhang_angle")]
+    pub overhang_angle: f64,
+    #[serde(default)]
+    pub printer: Printer,
+    #[serde(default)]
+    pub filament: Filament,
+    #[serde(default = "default_arrange_spacing")]
+    pub arrange_spacing: f64,
+    #[serde(default)]
+    pub sequential: bool,
+}
+
+fn default_fuzzy_skin_thickness() -> f64 {
+    0.3
+}
+
+fn default_fuzzy_skin_point_distance() -> f64 {
+    0.8
+}
+
+fn default_gap_fill_min_width() -> f64 {
+    0.1
+}
+
+fn default_bridge_speed() -> f64 {
+    25.0
+}
+
+fn default_bridge_flow_ratio() -> f64 {
+    0.8
+}
+
+fn default_bridge_fan_speed() -> f64 {
+    100.0
+}
+
+fn default_bridge_anchor_distance() -> f64 {
+    1.0
+}
+
+fn default_overhang_angle() -> f64 {
+    45.0
+}
+
+fn default_lightning_angle() -> f64 {
+    40.0
+}
+
+fn default_arrange_spacing() -> f64 {
+    5.0
 }
 
 impl Config {
     pub fn new(fh: File) -> NarsilResult<Config> {
-        Ok(serde_yaml::from_reader(BufReader::new(fh))?)
+        let config: Config = serde_yaml::from_reader(BufReader::new(fh))?;
+        config.filament.validate()?;
+        Ok(config)
     }
 
     pub fn discretized(&self, value: f64) -> i64 {
@@ -44,6 +166,10 @@ impl Config {
         self.discretized(self.shell_infill_overlap)
     }
 
+    pub fn gap_fill_min_width_dsc(&self) -> i64 {
+        self.discretized(self.gap_fill_min_width)
+    }
+
     pub fn interior_offset_dsc(&self) -> i64 {
         -(self.nozzle_diameter_dsc()
             + (self.nozzle_diameter_dsc() - self.shell_overlap_dsc())
@@ -59,15 +185,101 @@ impl Config {
         (self.bottom_thickness / self.layer_height) as usize
     }
 
+    pub fn discretized_area(&self, value: f64) -> f64 {
+        value / (self.resolution * self.resolution)
+    }
+
+    pub fn min_skin_area_dsc(&self) -> f64 {
+        self.discretized_area(self.min_skin_area)
+    }
+
+    pub fn skin_expansion_distance_dsc(&self) -> i64 {
+        self.discretized(self.skin_expansion_distance)
+    }
+
+    pub fn solid_infill_below_area_dsc(&self) -> f64 {
+        self.discretized_area(self.solid_infill_below_area)
+    }
+
+    pub fn bridge_anchor_distance_dsc(&self) -> i64 {
+        self.discretized(self.bridge_anchor_distance)
+    }
+
+    // Bridge lines are laid with less flow, so they sit closer together to
+    // keep touching
+    pub fn bridge_line_spacing_dsc(&self) -> i64 {
+        self.discretized(self.nozzle_diameter * self.bridge_flow_ratio.sqrt())
+    }
+
+    pub fn bridge_path_settings(&self) -> PathSettings {
+        PathSettings {
+            speed: self.bridge_speed,
+            flow: self.bridge_flow_ratio,
+            fan_speed: self.bridge_fan_speed,
+        }
+    }
+
+    pub fn solid_fill_angle(&self, layer: usize) -> f64 {
+        self.solid_fill_initial_angle + self.solid_fill_angle_increment * layer as f64
+    }
+
     pub fn solid_fill_line_spacing_dsc(&self) -> i64 {
         self.discretized(
             self.nozzle_diameter - self.nozzle_diameter * self.solid_fill_overlap_ratio,
         )
     }
 
+    // One gyroid period holds two walls in each direction
+    pub fn gyroid_period(&self) -> f64 {
+        std::f64::consts::PI * self.nozzle_diameter / self.sparse_fill_density
+    }
+
+    // Distance across the flats of a cell. Cells have two walls of their own
+    // plus doubled vertical walls, 4 side lengths per cell, which sets the
+    // size for the configured density unless one is given.
+    pub fn honeycomb_cell_size_dsc(&self) -> i64 {
+        self.discretized(
+            self.honeycomb_cell_size
+                .unwrap_or(8.0 * self.nozzle_diameter / (3.0 * self.sparse_fill_density)),
+        )
+    }
+
     pub fn sparse_fill_line_spacing_dsc(&self) -> i64 {
+        let line_sets = self.sparse_fill_pattern.line_sets() as f64;
         self.discretized(
-            self.nozzle_diameter * (1.0 - self.sparse_fill_density) / self.sparse_fill_density,
+            self.nozzle_diameter * (line_sets - self.sparse_fill_density)
+                / self.sparse_fill_density,
         )
     }
 }
+
+#[cfg(test)]
+impl Config {
+    pub fn default_test() -> Config {
+        serde_yaml::from_str(include_str!("../../conf/default.yaml")).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each line set puts down a nozzle wide line every spacing plus a line
+    fn extruded_density(config: &Config) -> f64 {
+        let spacing = config.sparse_fill_line_spacing_dsc() as f64 * config.resolution;
+        config.sparse_fill_pattern.line_sets() as f64 * config.nozzle_diameter
+            / (spacing + config.nozzle_diameter)
+    }
+
+    #[test]
+    fn sparse_fill_density_matches_across_patterns() {
+        let mut config = Config::default_test();
+        let linear = extruded_density(&config);
+        assert!((linear - config.sparse_fill_density).abs() < 1e-6);
+
+        for pattern in [FillPattern::Grid, FillPattern::Triangles].iter() {
+            config.sparse_fill_pattern = *pattern;
+            assert!((extruded_density(&config) - linear).abs() < 1e-6);
+        }
+    }
+}
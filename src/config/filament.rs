@@ -0,0 +1,71 @@
+use crate::error::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Filament {
+    #[serde(default)]
+    pub name: String,
+    // Percent the printed part shrinks along x, y and z as it cools
+    #[serde(default)]
+    pub shrinkage: [f64; 3],
+}
+
+impl Default for Filament {
+    fn default() -> Self {
+        Filament {
+            name: String::new(),
+            shrinkage: [0.0; 3],
+        }
+    }
+}
+
+impl Filament {
+    pub fn validate(&self) -> NarsilResult<()> {
+        match self.shrinkage.iter().find(|s| !(0.0..100.0).contains(*s)) {
+            Some(s) => Err(NarsilError::InvalidConfig(format!(
+                "filament shrinkage {}% is outside 0% to 100%",
+                s
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    // Scale that comes out at the modeled size once the part has shrunk
+    pub fn shrinkage_scale(&self) -> [f64; 3] {
+        [
+            100.0 / (100.0 - self.shrinkage[0]),
+            100.0 / (100.0 - self.shrinkage[1]),
+            100.0 / (100.0 - self.shrinkage[2]),
+        ]
+    }
+
+    pub fn compensates_shrinkage(&self) -> bool {
+        self.shrinkage.iter().any(|s| *s != 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filament(shrinkage: [f64; 3]) -> Filament {
+        Filament {
+            name: String::new(),
+            shrinkage,
+        }
+    }
+
+    #[test]
+    fn shrinkage_scales_up() {
+        let scale = filament([0.0, 20.0, 50.0]).shrinkage_scale();
+        assert_eq!(scale, [1.0, 1.25, 2.0]);
+    }
+
+    #[test]
+    fn shrinkage_out_of_range_is_rejected() {
+        assert!(filament([0.0, 1.5, 99.0]).validate().is_ok());
+        assert!(filament([100.0, 0.0, 0.0]).validate().is_err());
+        assert!(filament([0.0, 120.0, 0.0]).validate().is_err());
+        assert!(filament([0.0, 0.0, -1.0]).validate().is_err());
+    }
+}
@@ -1,5 +1,11 @@
 pub mod args;
 pub mod config;
+pub mod filament;
+pub mod plate;
+pub mod printer;
 
 pub use self::config::*;
 pub use self::args::*;
+pub use self::filament::*;
+pub use self::plate::*;
+pub use self::printer::*;
@@ -0,0 +1,141 @@
+use crate::error::*;
+use config::*;
+use serde::{Deserialize, Serialize};
+use serde_yaml;
+use std::fs::File;
+use std::io::BufReader;
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigOverrides {
+    pub layer_height: Option<f64>,
+    pub num_shells: Option<u64>,
+    pub sparse_fill_density: Option<f64>,
+}
+
+impl ConfigOverrides {
+    pub fn apply(&self, config: &Config) -> Config {
+        let mut config = config.clone();
+        if let Some(layer_height) = self.layer_height {
+            config.layer_height = layer_height;
+        }
+        if let Some(num_shells) = self.num_shells {
+            config.num_shells = num_shells;
+        }
+        if let Some(sparse_fill_density) = self.sparse_fill_density {
+            config.sparse_fill_density = sparse_fill_density;
+        }
+        config
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TransformDescription {
+    // Position on the bed, objects without one are arranged automatically
+    pub translate: Option<[f64; 2]>,
+    // Degrees around X, then Y, then Z. Objects without one can be auto-oriented
+    pub rotate: Option<[f64; 3]>,
+    #[serde(default = "default_scale")]
+    pub scale: [f64; 3],
+}
+
+fn default_scale() -> [f64; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+impl Default for TransformDescription {
+    fn default() -> Self {
+        TransformDescription {
+            translate: None,
+            rotate: None,
+            scale: default_scale(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ObjectDescription {
+    pub file: String,
+    #[serde(default)]
+    pub transform: TransformDescription,
+    #[serde(default = "default_instances")]
+    pub instances: usize,
+    #[serde(default)]
+    pub overrides: ConfigOverrides,
+}
+
+fn default_instances() -> usize {
+    1
+}
+
+impl ObjectDescription {
+    pub fn new(file: String) -> Self {
+        ObjectDescription {
+            file,
+            transform: TransformDescription::default(),
+            instances: default_instances(),
+            overrides: ConfigOverrides::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PlateDescription {
+    pub objects: Vec<ObjectDescription>,
+}
+
+impl PlateDescription {
+    pub fn new(fh: File) -> NarsilResult<PlateDescription> {
+        Ok(serde_yaml::from_reader(BufReader::new(fh))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_plate_parses() {
+        let plate: PlateDescription =
+            serde_yaml::from_str(include_str!("../../conf/plate_example.yaml")).unwrap();
+        assert_eq!(plate.objects.len(), 2);
+
+        let bracket = &plate.objects[0];
+        assert_eq!(bracket.file, "bracket.stl");
+        assert_eq!(bracket.instances, 4);
+        assert_eq!(bracket.overrides.num_shells, Some(4));
+        assert_eq!(bracket.overrides.layer_height, None);
+        assert!(bracket.transform.translate.is_none());
+        assert_eq!(bracket.transform.scale, [1.0, 1.0, 1.0]);
+
+        let cover = &plate.objects[1];
+        assert_eq!(cover.instances, 1);
+        assert_eq!(cover.transform.translate, Some([20.0, 20.0]));
+        assert_eq!(cover.transform.rotate, Some([180.0, 0.0, 0.0]));
+        assert_eq!(cover.overrides.layer_height, Some(0.1));
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let misspelled = "objects:\n  - file: bracket.stl\n    instance: 2\n";
+        assert!(serde_yaml::from_str::<PlateDescription>(misspelled).is_err());
+        let misspelled = "objects:\n  - file: bracket.stl\n    overrides:\n      shells: 2\n";
+        assert!(serde_yaml::from_str::<PlateDescription>(misspelled).is_err());
+    }
+
+    #[test]
+    fn overrides_replace_only_what_they_set() {
+        let config = Config::default_test();
+        let overrides = ConfigOverrides {
+            num_shells: Some(5),
+            ..Default::default()
+        };
+        let applied = overrides.apply(&config);
+        assert_eq!(applied.num_shells, 5);
+        assert_eq!(applied.layer_height, config.layer_height);
+        assert_eq!(applied.sparse_fill_density, config.sparse_fill_density);
+    }
+}
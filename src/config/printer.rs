@@ -0,0 +1,115 @@
+use geo::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BedShape {
+    Rectangle { width: f64, depth: f64 },
+    Circle { diameter: f64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExclusionZone {
+    pub min: [f64; 2],
+    pub max: [f64; 2],
+}
+
+// `origin` is where machine (0, 0) sits, measured from the front left corner
+// of the bed (of its bounding square for circular beds)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Printer {
+    pub bed: BedShape,
+    #[serde(default)]
+    pub origin: [f64; 2],
+    pub max_height: f64,
+    #[serde(default)]
+    pub exclusion_zones: Vec<ExclusionZone>,
+    // Radius around the nozzle that the print head occupies, and the height
+    // of the lowest part of the gantry above the nozzle tip
+    #[serde(default = "default_extruder_clearance_radius")]
+    pub extruder_clearance_radius: f64,
+    #[serde(default = "default_gantry_height")]
+    pub gantry_height: f64,
+}
+
+fn default_extruder_clearance_radius() -> f64 {
+    20.0
+}
+
+fn default_gantry_height() -> f64 {
+    20.0
+}
+
+impl Default for Printer {
+    fn default() -> Self {
+        Printer {
+            bed: BedShape::Rectangle {
+                width: 200.0,
+                depth: 200.0,
+            },
+            origin: [0.0, 0.0],
+            max_height: 200.0,
+            exclusion_zones: Vec::new(),
+            extruder_clearance_radius: default_extruder_clearance_radius(),
+            gantry_height: default_gantry_height(),
+        }
+    }
+}
+
+impl ExclusionZone {
+    pub fn rect(&self) -> geo::Rect<f64> {
+        geo::Rect::new(
+            geo::Coordinate {
+                x: self.min[0],
+                y: self.min[1],
+            },
+            geo::Coordinate {
+                x: self.max[0],
+                y: self.max[1],
+            },
+        )
+    }
+}
+
+impl Printer {
+    pub fn bed_rect(&self) -> geo::Rect<f64> {
+        let (width, depth) = match self.bed {
+            BedShape::Rectangle { width, depth } => (width, depth),
+            BedShape::Circle { diameter } => (diameter, diameter),
+        };
+        geo::Rect::new(
+            geo::Coordinate {
+                x: -self.origin[0],
+                y: -self.origin[1],
+            },
+            geo::Coordinate {
+                x: width - self.origin[0],
+                y: depth - self.origin[1],
+            },
+        )
+    }
+
+    pub fn bed_contains(&self, footprint: &geo::Polygon<f64>) -> bool {
+        let bed = self.bed_rect();
+        match self.bed {
+            BedShape::Rectangle { .. } => footprint.exterior().0.iter().all(|c| {
+                c.x >= bed.min().x && c.x <= bed.max().x && c.y >= bed.min().y && c.y <= bed.max().y
+            }),
+            BedShape::Circle { diameter } => {
+                let center = bed.center();
+                let radius = diameter / 2.0;
+                footprint
+                    .exterior()
+                    .0
+                    .iter()
+                    .all(|c| (c.x - center.x).hypot(c.y - center.y) <= radius)
+            }
+        }
+    }
+
+    pub fn excluded_by(&self, footprint: &geo::Polygon<f64>) -> Option<usize> {
+        self.exclusion_zones
+            .iter()
+            .position(|zone| zone.rect().to_polygon().intersects(footprint))
+    }
+}
@@ -1,3 +1,7 @@
 pub mod nearest_neighbor;
+pub mod ordering;
+pub mod zigzag;
 
 pub use crate::connect::nearest_neighbor::*;
+pub use crate::connect::ordering::*;
+pub use crate::connect::zigzag::*;
@@ -0,0 +1,83 @@
+use crate::config::Config;
+use crate::types::*;
+
+// Order ranks are printed in, rank 0 being the outer wall
+fn rank_order(wall_order: WallOrder, num_ranks: usize) -> Vec<usize> {
+    match wall_order {
+        WallOrder::OuterInner => (0..num_ranks).collect(),
+        WallOrder::InnerOuter => (0..num_ranks).rev().collect(),
+        // Inner walls from the inside out, then the outer wall, then the wall
+        // just inside it
+        WallOrder::InnerOuterInner if num_ranks > 2 => {
+            (2..num_ranks).rev().chain(vec![0, 1].into_iter()).collect()
+        }
+        WallOrder::InnerOuterInner => (0..num_ranks).collect(),
+    }
+}
+
+// Sequences one region's shells by wall order, with the walls around holes
+// either first or alongside the outer contour's walls of the same rank
+pub fn order_shells(shells: &Shells, config: &Config) -> Vec<TaggedPath> {
+    let ranks = rank_order(config.wall_order, shells.shells.len());
+    let mut singles = shells.to_single_shells();
+    singles.sort_by_key(|single| {
+        let hole_pass = if config.holes_first && !single.hole { 1 } else { 0 };
+        let rank_pass = ranks.iter().position(|rank| *rank == single.rank).unwrap();
+        (hole_pass, rank_pass)
+    });
+    singles
+        .into_iter()
+        .map(|single| TaggedPath {
+            tag: PathTag::Shell,
+            path: single.shell,
+            widths: single.widths,
+            z: None,
+            settings: None,
+        })
+        .collect()
+}
+
+pub fn order_layer_shells(layer_shells: &LayerShells, config: &Config) -> Vec<TaggedPath> {
+    layer_shells
+        .0
+        .iter()
+        .map(|shells| order_shells(shells, config))
+        .flatten()
+        .collect()
+}
+
+fn is_wall(tag: &PathTag) -> bool {
+    match tag {
+        PathTag::Shell | PathTag::ThinWall | PathTag::GapFill => true,
+        _ => false,
+    }
+}
+
+// Walls go ahead of infill unless infill is set to print first
+pub fn order_layer(paths: Vec<TaggedPath>, config: &Config) -> Vec<TaggedPath> {
+    if !config.infill_first {
+        return paths;
+    }
+    let (walls, rest): (Vec<TaggedPath>, Vec<TaggedPath>) =
+        paths.into_iter().partition(|path| is_wall(&path.tag));
+    rest.into_iter().chain(walls.into_iter()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rank_orders() {
+        assert_eq!(rank_order(WallOrder::OuterInner, 4), vec![0, 1, 2, 3]);
+        assert_eq!(rank_order(WallOrder::InnerOuter, 4), vec![3, 2, 1, 0]);
+        assert_eq!(rank_order(WallOrder::InnerOuterInner, 4), vec![3, 2, 0, 1]);
+    }
+
+    #[test]
+    fn inner_outer_inner_with_two_walls_or_fewer() {
+        assert_eq!(rank_order(WallOrder::InnerOuterInner, 2), vec![0, 1]);
+        assert_eq!(rank_order(WallOrder::InnerOuterInner, 1), vec![0]);
+        assert!(rank_order(WallOrder::InnerOuterInner, 0).is_empty());
+    }
+}
@@ -0,0 +1,297 @@
+use crate::types::*;
+
+// Fill lines are laid perpendicular to the fill angle's normal, so they are
+// sorted across by `u` and along by `v`
+struct FillSegment {
+    u: f64,
+    lo: Coordinate,
+    hi: Coordinate,
+}
+
+fn fill_segments(lines: &MultiLineString, angle: f64) -> Vec<FillSegment> {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let u = |c: &Coordinate| c.x as f64 * cos + c.y as f64 * sin;
+    let v = |c: &Coordinate| -(c.x as f64) * sin + c.y as f64 * cos;
+
+    let mut segments: Vec<FillSegment> = lines
+        .0
+        .iter()
+        .filter(|line| line.0.len() >= 2)
+        .map(|line| {
+            let a = line.0[0];
+            let b = *line.0.last().unwrap();
+            let (lo, hi) = if v(&a) <= v(&b) { (a, b) } else { (b, a) };
+            FillSegment {
+                u: (u(&a) + u(&b)) / 2.0,
+                lo,
+                hi,
+            }
+        })
+        .collect();
+
+    segments.sort_by(|a, b| {
+        a.u.partial_cmp(&b.u)
+            .unwrap()
+            .then(v(&a.lo).partial_cmp(&v(&b.lo)).unwrap())
+    });
+    segments
+}
+
+fn dist(a: &Coordinate, b: &Coordinate) -> f64 {
+    ((a.x - b.x) as f64).hypot((a.y - b.y) as f64)
+}
+
+fn segment_dist(p: &Coordinate, a: &Coordinate, b: &Coordinate) -> f64 {
+    let (dx, dy) = ((b.x - a.x) as f64, (b.y - a.y) as f64);
+    let len_sq = dx * dx + dy * dy;
+    if len_sq == 0.0 {
+        return dist(p, a);
+    }
+    let t = ((((p.x - a.x) as f64) * dx + ((p.y - a.y) as f64) * dy) / len_sq)
+        .max(0.0)
+        .min(1.0);
+    ((p.x - a.x) as f64 - dx * t).hypot((p.y - a.y) as f64 - dy * t)
+}
+
+// Ring and edge a clipped line end sits on
+fn locate(rings: &Vec<LineString>, p: &Coordinate, tolerance: f64) -> Option<(usize, usize)> {
+    for (r, ring) in rings.iter().enumerate() {
+        for e in 0..ring.0.len().saturating_sub(1) {
+            if segment_dist(p, &ring.0[e], &ring.0[e + 1]) <= tolerance {
+                return Some((r, e));
+            }
+        }
+    }
+    None
+}
+
+// Shorter way around the ring from `from` on edge `from_edge` to `to` on
+// edge `to_edge`, without the end points
+fn boundary_path(
+    ring: &LineString,
+    from: &Coordinate,
+    from_edge: usize,
+    to: &Coordinate,
+    to_edge: usize,
+) -> (Vec<Coordinate>, f64) {
+    if from_edge == to_edge {
+        return (Vec::new(), dist(from, to));
+    }
+
+    let n = ring.0.len() - 1;
+    let walk = |forward: bool| -> (Vec<Coordinate>, f64) {
+        let mut points = Vec::new();
+        let mut length = 0.0;
+        let mut last = *from;
+        let mut i = if forward { (from_edge + 1) % n } else { from_edge };
+        let end = if forward { (to_edge + 1) % n } else { to_edge };
+        if forward {
+            while i != end {
+                length += dist(&last, &ring.0[i]);
+                last = ring.0[i];
+                points.push(last);
+                i = (i + 1) % n;
+            }
+        } else {
+            while i != end {
+                length += dist(&last, &ring.0[i]);
+                last = ring.0[i];
+                points.push(last);
+                i = (i + n - 1) % n;
+            }
+        }
+        length += dist(&last, to);
+        (points, length)
+    };
+
+    let forward = walk(true);
+    let backward = walk(false);
+    if forward.1 <= backward.1 {
+        forward
+    } else {
+        backward
+    }
+}
+
+// Joins neighboring fill lines end to end into zig-zags. Connectors follow
+// the region outline, and a line only joins the next one over when that
+// connector is at most two line spacings long.
+pub fn zigzag_connect(
+    lines: &MultiLineString,
+    region: &MultiPolygon,
+    angle: f64,
+    spacing: i64,
+) -> MultiLineString {
+    let segments = fill_segments(lines, angle);
+    let rings: Vec<LineString> = region
+        .0
+        .iter()
+        .map(|poly| {
+            std::iter::once(poly.exterior().clone()).chain(poly.interiors().iter().cloned())
+        })
+        .flatten()
+        .collect();
+
+    let tolerance = (spacing as f64 / 50.0).max(2.0);
+    let max_connect = 2.0 * spacing as f64;
+
+    let mut columns: Vec<usize> = Vec::with_capacity(segments.len());
+    let mut column = 0;
+    for (i, segment) in segments.iter().enumerate() {
+        if i > 0 && segment.u - segments[i - 1].u > spacing as f64 / 2.0 {
+            column += 1;
+        }
+        columns.push(column);
+    }
+
+    let mut used = vec![false; segments.len()];
+    let mut paths = MultiLineString::default();
+
+    for start in 0..segments.len() {
+        if used[start] {
+            continue;
+        }
+
+        let mut path: Vec<Coordinate> = Vec::new();
+        let mut cur = start;
+        let mut going_up = true;
+        loop {
+            used[cur] = true;
+            let segment = &segments[cur];
+            let (enter, exit) = if going_up {
+                (segment.lo, segment.hi)
+            } else {
+                (segment.hi, segment.lo)
+            };
+            path.push(enter);
+            path.push(exit);
+
+            let exit_at = match locate(&rings, &exit, tolerance) {
+                Some(found) => found,
+                None => break,
+            };
+
+            let next = (0..segments.len())
+                .filter(|c| !used[*c] && columns[*c] == columns[cur] + 1)
+                .filter_map(|c| {
+                    let entry = if going_up { segments[c].hi } else { segments[c].lo };
+                    let (ring, edge) = locate(&rings, &entry, tolerance)?;
+                    if ring != exit_at.0 {
+                        return None;
+                    }
+                    let (connector, length) =
+                        boundary_path(&rings[ring], &exit, exit_at.1, &entry, edge);
+                    if length > max_connect {
+                        return None;
+                    }
+                    Some((c, connector, length))
+                })
+                .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+
+            match next {
+                Some((c, connector, _)) => {
+                    path.extend(connector.into_iter());
+                    cur = c;
+                    going_up = !going_up;
+                }
+                None => break,
+            }
+        }
+
+        paths.push(path.into());
+    }
+
+    paths
+}
+
+// Lines ordered across the region and all laid the same way, so each one is
+// put down next to the one before it from the same side
+pub fn monotonic_order(lines: &MultiLineString, angle: f64) -> MultiLineString {
+    fill_segments(lines, angle)
+        .into_iter()
+        .map(|segment| -> LineString { vec![segment.lo, segment.hi].into() })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square_ring() -> LineString {
+        vec![(0, 0), (100, 0), (100, 100), (0, 100), (0, 0)].into()
+    }
+
+    fn vertical_lines(xs: impl Iterator<Item = i64>) -> MultiLineString {
+        xs.enumerate()
+            .map(|(i, x)| -> LineString {
+                // Alternate directions, as clipping leaves them
+                if i % 2 == 0 {
+                    vec![(x, 0), (x, 100)].into()
+                } else {
+                    vec![(x, 100), (x, 0)].into()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn boundary_path_takes_the_shorter_way_around() {
+        let ring = square_ring();
+        let from = Coordinate { x: 90, y: 0 };
+        let to = Coordinate { x: 90, y: 100 };
+        let (points, length) = boundary_path(&ring, &from, 0, &to, 2);
+        assert_eq!(points, vec![Coordinate { x: 100, y: 0 }, Coordinate { x: 100, y: 100 }]);
+        assert_eq!(length, 120.0);
+
+        let (points, length) = boundary_path(&ring, &to, 2, &from, 0);
+        assert_eq!(points, vec![Coordinate { x: 100, y: 100 }, Coordinate { x: 100, y: 0 }]);
+        assert_eq!(length, 120.0);
+    }
+
+    #[test]
+    fn boundary_path_on_one_edge() {
+        let from = Coordinate { x: 10, y: 0 };
+        let to = Coordinate { x: 30, y: 0 };
+        let (points, length) = boundary_path(&square_ring(), &from, 0, &to, 0);
+        assert!(points.is_empty());
+        assert_eq!(length, 20.0);
+    }
+
+    #[test]
+    fn zigzag_joins_neighboring_lines() {
+        let region: MultiPolygon = vec![Polygon::new(square_ring(), vec![])].into();
+        let lines = vertical_lines((1..10).map(|i| i * 10));
+
+        let paths = zigzag_connect(&lines, &region, 0.0, 10);
+        assert_eq!(paths.0.len(), 1);
+        let path = &paths.0[0];
+        assert_eq!(path.0.len(), 18);
+        assert_eq!(path.0[0], Coordinate { x: 10, y: 0 });
+        assert_eq!(path.0[1], Coordinate { x: 10, y: 100 });
+        assert_eq!(path.0[2], Coordinate { x: 20, y: 100 });
+        assert_eq!(path.0[17], Coordinate { x: 90, y: 100 });
+    }
+
+    #[test]
+    fn zigzag_leaves_distant_lines_apart() {
+        let region: MultiPolygon = vec![Polygon::new(square_ring(), vec![])].into();
+        let lines = vertical_lines(vec![10, 90].into_iter());
+
+        assert_eq!(zigzag_connect(&lines, &region, 0.0, 10).0.len(), 2);
+    }
+
+    #[test]
+    fn monotonic_lines_run_the_same_way() {
+        let lines = vertical_lines(vec![30, 10, 20].into_iter());
+        let ordered = monotonic_order(&lines, 0.0);
+        let ends: Vec<(Coordinate, Coordinate)> =
+            ordered.0.iter().map(|l| (l.0[0], l.0[1])).collect();
+        assert_eq!(
+            ends,
+            vec![10, 20, 30]
+                .into_iter()
+                .map(|x| (Coordinate { x, y: 0 }, Coordinate { x, y: 100 }))
+                .collect::<Vec<_>>()
+        );
+    }
+}
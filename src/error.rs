@@ -1,6 +1,8 @@
 use model_file;
+use plate;
 use serde_yaml;
 use slicer;
+use spiral;
 use std::error;
 use std::fmt;
 
@@ -11,6 +13,11 @@ pub enum NarsilError {
     IO(std::io::Error),
     Serialize(serde_yaml::Error),
     Collate(geo_collate::CollateError),
+    Plate(plate::PlateError),
+    Validation(plate::ValidationError),
+    UnknownPattern(String),
+    Spiral(spiral::SpiralError),
+    InvalidConfig(String),
     Unknown,
 }
 
@@ -24,6 +31,11 @@ impl error::Error for NarsilError {
             Self::IO(e) => Some(e),
             Self::Serialize(e) => Some(e),
             Self::Collate(e) => Some(e),
+            Self::Plate(e) => Some(e),
+            Self::Validation(e) => Some(e),
+            Self::UnknownPattern(_) => None,
+            Self::Spiral(e) => Some(e),
+            Self::InvalidConfig(_) => None,
             Self::Unknown => None,
         }
     }
@@ -37,6 +49,11 @@ impl fmt::Display for NarsilError {
             Self::IO(e) => write!(f, "{}", e),
             Self::Serialize(e) => write!(f, "{}", e),
             Self::Collate(e) => write!(f, "{}", e),
+            Self::Plate(e) => write!(f, "Error laying out plate: {}", e),
+            Self::Validation(e) => write!(f, "Object outside printable volume: {}", e),
+            Self::UnknownPattern(name) => write!(f, "No fill pattern registered as {}", name),
+            Self::Spiral(e) => write!(f, "{}", e),
+            Self::InvalidConfig(reason) => write!(f, "Invalid config: {}", reason),
             Self::Unknown => write!(f, "Unknown error"),
         }
     }
@@ -66,6 +83,18 @@ impl From<geo_collate::CollateError> for NarsilError {
     }
 }
 
+impl From<plate::PlateError> for NarsilError {
+    fn from(other: plate::PlateError) -> Self {
+        Self::Plate(other)
+    }
+}
+
+impl From<plate::ValidationError> for NarsilError {
+    fn from(other: plate::ValidationError) -> Self {
+        Self::Validation(other)
+    }
+}
+
 impl From<serde_yaml::Error> for NarsilError {
     fn from(other: serde_yaml::Error) -> Self {
         Self::Serialize(other)
@@ -77,3 +106,9 @@ impl From<()> for NarsilError {
         Self::Unknown
     }
 }
+
+impl From<spiral::SpiralError> for NarsilError {
+    fn from(other: spiral::SpiralError) -> Self {
+        Self::Spiral(other)
+    }
+}
@@ -0,0 +1,200 @@
+use crate::geometry::polygon_utils::signed_ring_area;
+use crate::types::*;
+
+type FPoint = (f64, f64);
+
+fn dist(a: FPoint, b: FPoint) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+// Distance along the ray to the nearest polygon edge, ignoring hits right at
+// the start
+fn ray_hit(origin: FPoint, dir: FPoint, edges: &Vec<(FPoint, FPoint)>) -> Option<f64> {
+    let mut nearest: Option<f64> = None;
+    for &(a, b) in edges.iter() {
+        let e = (b.0 - a.0, b.1 - a.1);
+        let denom = dir.0 * e.1 - dir.1 * e.0;
+        if denom.abs() < 1e-12 {
+            continue;
+        }
+        let w = (a.0 - origin.0, a.1 - origin.1);
+        let t = (w.0 * e.1 - w.1 * e.0) / denom;
+        let s = (w.0 * dir.1 - w.1 * dir.0) / denom;
+        if t > 1.0 && s >= 0.0 && s <= 1.0 && nearest.map_or(true, |n| t < n) {
+            nearest = Some(t);
+        }
+    }
+    nearest
+}
+
+// Samples points along the polygon outline every `step`, casts a ray inward
+// from each to the opposite side, and keeps the midpoint and the distance
+// across. Both sides of a thin feature land on the same centers.
+pub fn center_samples(poly: &Polygon, step: f64, max_width: f64) -> Vec<(FPoint, f64)> {
+    let rings: Vec<(&LineString, bool)> = std::iter::once((poly.exterior(), true))
+        .chain(poly.interiors().iter().map(|r| (r, false)))
+        .collect();
+
+    let edges: Vec<(FPoint, FPoint)> = rings
+        .iter()
+        .map(|(ring, _)| {
+            ring.0
+                .windows(2)
+                .map(|w| ((w[0].x as f64, w[0].y as f64), (w[1].x as f64, w[1].y as f64)))
+        })
+        .flatten()
+        .collect();
+
+    let mut samples = Vec::new();
+    for (ring, exterior) in rings.iter() {
+        // The polygon's inside is left of a counter clockwise outer ring and
+        // right of a counter clockwise hole
+        let left = (signed_ring_area(ring) > 0.0) == *exterior;
+        for w in ring.0.windows(2) {
+            let a = (w[0].x as f64, w[0].y as f64);
+            let b = (w[1].x as f64, w[1].y as f64);
+            let length = dist(a, b);
+            if length == 0.0 {
+                continue;
+            }
+            let along = ((b.0 - a.0) / length, (b.1 - a.1) / length);
+            let inward = if left {
+                (-along.1, along.0)
+            } else {
+                (along.1, -along.0)
+            };
+
+            let count = (length / step).ceil().max(1.0) as usize;
+            for i in 0..count {
+                let t = (i as f64 + 0.5) * length / count as f64;
+                let origin = (a.0 + along.0 * t, a.1 + along.1 * t);
+                if let Some(width) = ray_hit(origin, inward, &edges) {
+                    if width <= max_width {
+                        samples.push((
+                            (origin.0 + inward.0 * width / 2.0, origin.1 + inward.1 * width / 2.0),
+                            width,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    samples
+}
+
+// Approximate medial axis of a thin polygon as open center lines, with the
+// width of the polygon at each vertex. Center samples closer than a step
+// apart are merged and the rest chained to their nearest neighbor.
+pub fn medial_axis(poly: &Polygon, step: f64, max_width: f64) -> Vec<(LineString, Vec<i64>)> {
+    let samples = center_samples(poly, step, max_width);
+    let mut used = vec![false; samples.len()];
+    let mut axes = Vec::new();
+
+    let take = |from: usize, used: &mut Vec<bool>| {
+        for (i, sample) in samples.iter().enumerate() {
+            if !used[i] && dist(sample.0, samples[from].0) < step * 0.75 {
+                used[i] = true;
+            }
+        }
+        used[from] = true;
+    };
+
+    let nearest = |from: FPoint, used: &Vec<bool>| -> Option<usize> {
+        samples
+            .iter()
+            .enumerate()
+            .filter(|(i, s)| !used[*i] && dist(s.0, from) <= step * 2.0)
+            .min_by(|a, b| dist(a.1 .0, from).partial_cmp(&dist(b.1 .0, from)).unwrap())
+            .map(|(i, _)| i)
+    };
+
+    for start in 0..samples.len() {
+        if used[start] {
+            continue;
+        }
+        take(start, &mut used);
+
+        let mut forward = vec![start];
+        while let Some(next) = nearest(samples[*forward.last().unwrap()].0, &used) {
+            take(next, &mut used);
+            forward.push(next);
+        }
+        let mut chain = vec![start];
+        while let Some(next) = nearest(samples[*chain.last().unwrap()].0, &used) {
+            take(next, &mut used);
+            chain.push(next);
+        }
+        chain.reverse();
+        chain.extend(forward.into_iter().skip(1));
+
+        if chain.len() < 2 {
+            continue;
+        }
+        let path: LineString = chain
+            .iter()
+            .map(|i| Coordinate {
+                x: samples[*i].0 .0 as i64,
+                y: samples[*i].0 .1 as i64,
+            })
+            .collect::<Vec<Coordinate>>()
+            .into();
+        let widths = chain.iter().map(|i| samples[*i].1 as i64).collect();
+        axes.push((path, widths));
+    }
+
+    axes
+}
+
+pub fn path_length(path: &LineString) -> f64 {
+    path.0
+        .windows(2)
+        .map(|w| ((w[1].x - w[0].x) as f64).hypot((w[1].y - w[0].y) as f64))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counter clockwise rectangle ring in millimeters
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> LineString {
+        let dsc = |mm: f64| (mm * 1e7) as i64;
+        let (x0, y0, x1, y1) = (dsc(x0), dsc(y0), dsc(x1), dsc(y1));
+        vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1), (x0, y0)].into()
+    }
+
+    #[test]
+    fn thin_strip_has_one_center_line() {
+        let strip = Polygon::new(rect(0.0, 0.0, 10.0, 0.3), Vec::new());
+        let axes = medial_axis(&strip, 2e6, 8e6);
+
+        assert_eq!(axes.len(), 1);
+        let (path, widths) = &axes[0];
+        assert_eq!(path.0.len(), widths.len());
+        assert!(path_length(path) > 9.5e7);
+        assert!(path.0.iter().all(|c| (c.y - 1_500_000).abs() < 1_000));
+        assert!(widths.iter().all(|w| (w - 3_000_000).abs() < 1_000));
+    }
+
+    #[test]
+    fn holes_cast_away_from_the_hole() {
+        // A 1mm wide frame, with the hole wound either way
+        for reverse in [false, true].iter() {
+            let mut hole = rect(1.0, 1.0, 9.0, 9.0);
+            if *reverse {
+                hole.0.reverse();
+            }
+            let frame = Polygon::new(rect(0.0, 0.0, 10.0, 10.0), vec![hole]);
+            let samples = center_samples(&frame, 5e6, 1.5e7);
+
+            // 16 per side of the outline away from the corners, 16 per side of
+            // the hole
+            assert_eq!(samples.len(), 128);
+            for ((x, y), width) in samples.iter() {
+                assert!((width - 1e7).abs() < 1e3);
+                let edge = x.min(*y).min(1e8 - x).min(1e8 - y);
+                assert!((edge - 5e6).abs() < 1e3);
+            }
+        }
+    }
+}
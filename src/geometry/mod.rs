@@ -1,2 +1,3 @@
 pub mod linestring_utils;
-pub mod rect_utils;
+pub mod medial_axis;
+pub mod polygon_utils;
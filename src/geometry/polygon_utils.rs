@@ -0,0 +1,48 @@
+use crate::types::*;
+
+// Area in floating point, squared discretized units overflow i64 on large
+// parts. Positive for counter clockwise rings.
+pub fn signed_ring_area(ring: &LineString) -> f64 {
+    ring.0
+        .windows(2)
+        .map(|w| w[0].x as f64 * w[1].y as f64 - w[1].x as f64 * w[0].y as f64)
+        .sum::<f64>()
+        / 2.0
+}
+
+fn ring_area(ring: &LineString) -> f64 {
+    signed_ring_area(ring).abs()
+}
+
+pub trait IntArea {
+    fn area_f64(&self) -> f64;
+}
+
+impl IntArea for Polygon {
+    fn area_f64(&self) -> f64 {
+        ring_area(self.exterior()) - self.interiors().iter().map(ring_area).sum::<f64>()
+    }
+}
+
+impl IntArea for MultiPolygon {
+    fn area_f64(&self) -> f64 {
+        self.0.iter().map(|p| p.area_f64()).sum()
+    }
+}
+
+// Area weighted center of the outer ring
+pub fn centroid_f64(poly: &Polygon) -> (f64, f64) {
+    let ring = &poly.exterior().0;
+    let (mut cx, mut cy, mut area) = (0.0, 0.0, 0.0);
+    for w in ring.windows(2) {
+        let (x0, y0, x1, y1) = (w[0].x as f64, w[0].y as f64, w[1].x as f64, w[1].y as f64);
+        let cross = x0 * y1 - x1 * y0;
+        cx += (x0 + x1) * cross;
+        cy += (y0 + y1) * cross;
+        area += cross;
+    }
+    if area == 0.0 {
+        return (ring[0].x as f64, ring[0].y as f64);
+    }
+    (cx / (3.0 * area), cy / (3.0 * area))
+}
@@ -1,73 +1,611 @@
-use crate::captures::*;
 //use crate::config::*;
+use crate::mesh::Octree;
 use crate::types::*;
 use crate::geometry::linestring_utils::*;
-use crate::geometry::rect_utils::*;
 
 use geo::bounding_rect::*;
-use simple_generator::Generator;
-use simple_generator::*;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::f64;
 
-pub fn rotated_fill(
+// Fill rotated about the origin, so fills of different bounds line up
+pub fn anchored_rotated_fill(
     fill_func: &impl Fn(Rect) -> MultiLineString,
     angle: f64,
     bounds: &Rect,
 ) -> MultiLineString {
+    let origin = Point::new(0, 0);
     fill_func(
         bounds
             .to_polygon()
             .exterior()
-            .rotate_around_point(-angle, bounds.center())
+            .rotate_around_point(-angle, origin)
             .bounding_rect()
             .unwrap(),
     )
-    .rotate_around_point(angle, bounds.center())
+    .rotate_around_point(angle, origin)
 }
 
-pub fn linear_fill_bounds(spacing: i64, bounds: Rect) -> MultiLineString {
+const GYROID_SAMPLES_PER_PERIOD: f64 = 24.0;
+
+fn gyroid(x: f64, y: f64, z: f64) -> f64 {
+    x.sin() * y.cos() + y.sin() * z.cos() + z.sin() * x.cos()
+}
+
+// Horizontal or vertical grid edge, by the grid index of its lower left end
+type EdgeKey = (bool, i64, i64);
+
+fn chain_segments(
+    segments: &Vec<(EdgeKey, EdgeKey)>,
+    points: &BTreeMap<EdgeKey, Coordinate>,
+) -> MultiLineString {
+    let mut adjacency = BTreeMap::<EdgeKey, Vec<EdgeKey>>::new();
+    for (a, b) in segments.iter() {
+        adjacency.entry(*a).or_insert_with(Vec::new).push(*b);
+        adjacency.entry(*b).or_insert_with(Vec::new).push(*a);
+    }
+
+    // Open curves start from their ends, whatever is left over is closed loops
+    let starts: Vec<EdgeKey> = adjacency
+        .iter()
+        .filter(|(_, n)| n.len() == 1)
+        .map(|(k, _)| *k)
+        .chain(adjacency.keys().cloned())
+        .collect();
+
+    let mut visited = BTreeSet::<EdgeKey>::new();
     let mut lines = MultiLineString::default();
-    let mut line_pos = bounds.min().x;
+    for start in starts {
+        if visited.contains(&start) {
+            continue;
+        }
+        visited.insert(start);
+        let mut line = vec![points[&start]];
+        let mut cur = start;
+        loop {
+            match adjacency[&cur].iter().find(|n| !visited.contains(n)) {
+                Some(next) => {
+                    visited.insert(*next);
+                    line.push(points[next]);
+                    cur = *next;
+                }
+                None => {
+                    if line.len() > 2 && adjacency[&cur].contains(&start) {
+                        line.push(points[&start]);
+                    }
+                    break;
+                }
+            }
+        }
+        if line.len() > 1 {
+            lines.push(geo::LineString(line));
+        }
+    }
 
-    while line_pos <= bounds.max().x {
-        lines.push(geo::LineString(vec![
-            Coordinate {
-                x: line_pos,
-                y: bounds.min().y,
-            },
-            Coordinate {
-                x: line_pos,
-                y: bounds.max().y,
-            },
-        ]));
-        line_pos += spacing;
+    lines
+}
+
+// Gyroid cross section at height `z`, traced with marching squares on a grid
+// anchored at the origin and cut to the bounds
+pub fn gyroid_fill_bounds(period: f64, z: f64, resolution: f64, bounds: Rect) -> MultiLineString {
+    let k = 2.0 * f64::consts::PI / period;
+    let step = period / GYROID_SAMPLES_PER_PERIOD;
+
+    let i0 = (bounds.min().x as f64 * resolution / step).floor() as i64;
+    let i1 = (bounds.max().x as f64 * resolution / step).ceil() as i64;
+    let j0 = (bounds.min().y as f64 * resolution / step).floor() as i64;
+    let j1 = (bounds.max().y as f64 * resolution / step).ceil() as i64;
+
+    let value = |i: i64, j: i64| gyroid(i as f64 * step * k, j as f64 * step * k, z * k);
+    let values: Vec<Vec<f64>> = (j0..=j1)
+        .map(|j| (i0..=i1).map(|i| value(i, j)).collect())
+        .collect();
+    let at = |i: i64, j: i64| values[(j - j0) as usize][(i - i0) as usize];
+
+    let crossing = |a: (i64, i64), b: (i64, i64)| -> Coordinate {
+        let va = at(a.0, a.1);
+        let vb = at(b.0, b.1);
+        let t = va / (va - vb);
+        let x = (a.0 as f64 + (b.0 - a.0) as f64 * t) * step;
+        let y = (a.1 as f64 + (b.1 - a.1) as f64 * t) * step;
+        Coordinate {
+            x: (x / resolution) as i64,
+            y: (y / resolution) as i64,
+        }
+    };
+
+    let mut points = BTreeMap::<EdgeKey, Coordinate>::new();
+    let mut segments = Vec::<(EdgeKey, EdgeKey)>::new();
+
+    for j in j0..j1 {
+        for i in i0..i1 {
+            let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+            let inside: Vec<bool> = corners.iter().map(|c| at(c.0, c.1) > 0.0).collect();
+            // bottom, right, top, left
+            let edges: [(EdgeKey, usize, usize); 4] = [
+                ((true, i, j), 0, 1),
+                ((false, i + 1, j), 1, 2),
+                ((true, i, j + 1), 3, 2),
+                ((false, i, j), 0, 3),
+            ];
+
+            let crossed: Vec<usize> = (0..4)
+                .filter(|e| inside[edges[*e].1] != inside[edges[*e].2])
+                .collect();
+
+            for e in crossed.iter() {
+                let (key, a, b) = edges[*e];
+                points
+                    .entry(key)
+                    .or_insert_with(|| crossing(corners[a], corners[b]));
+            }
+
+            match crossed.len() {
+                2 => segments.push((edges[crossed[0]].0, edges[crossed[1]].0)),
+                4 => {
+                    let center = (at(i, j) + at(i + 1, j) + at(i + 1, j + 1) + at(i, j + 1)) / 4.0;
+                    if (center > 0.0) == inside[0] {
+                        segments.push((edges[0].0, edges[1].0));
+                        segments.push((edges[2].0, edges[3].0));
+                    } else {
+                        segments.push((edges[0].0, edges[3].0));
+                        segments.push((edges[1].0, edges[2].0));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    clip_to_rect(&chain_segments(&segments, &points), &bounds)
+}
+
+// Hexagons traced as zig-zag columns, shared walls pushed `offset` apart
+pub fn honeycomb_fill_bounds(cell_size: i64, offset: i64, bounds: Rect) -> MultiLineString {
+    let width = cell_size as f64;
+    let side = width / 3.0_f64.sqrt();
+    let row_height = 1.5 * side;
+    let offset = offset as f64;
+
+    let m0 = (bounds.min().x as f64 / width).floor() as i64 - 1;
+    let m1 = (bounds.max().x as f64 / width).ceil() as i64;
+    let r0 = (bounds.min().y as f64 / row_height).floor() as i64 - 1;
+    let r1 = (bounds.max().y as f64 / row_height).ceil() as i64 + 1;
+
+    let column = |wall_x: &dyn Fn(bool) -> f64| -> LineString {
+        (r0..=r1)
+            .map(|r| {
+                let x = wall_x(r.rem_euclid(2) == 0) as i64;
+                let y = r as f64 * row_height;
+                vec![
+                    Coordinate {
+                        x,
+                        y: (y - side / 2.0) as i64,
+                    },
+                    Coordinate {
+                        x,
+                        y: (y + side / 2.0) as i64,
+                    },
+                ]
+            })
+            .flatten()
+            .collect::<Vec<Coordinate>>()
+            .into()
+    };
+
+    let mut lines = MultiLineString::default();
+    for m in m0..=m1 {
+        let left = m as f64 * width;
+        lines.push(column(&|even| {
+            if even {
+                left + width / 2.0 - offset
+            } else {
+                left + offset
+            }
+        }));
+        lines.push(column(&|even| {
+            if even {
+                left + width / 2.0 + offset
+            } else {
+                left + width - offset
+            }
+        }));
     }
 
     lines
 }
 
-pub fn rotating_fill_gen<'a>(
-    fill_func: impl Fn(Rect) -> MultiLineString + 'a,
-    init_angle: f64,
-    rotate_angle: f64,
-    bounds: Rect,
-) -> impl Generator<Item = MultiLineString> + Captures<'a> {
-    GeneratorFunc::new(move || {
-        let mut angle = init_angle;
-        let mut cache = HashMap::<i64, MultiLineString>::new();
-        //let fill_func = &fill_func;
-        move || {
-            let approx_angle = (angle * 10000.0) as i64;
-            let result = match cache.get(&approx_angle) {
-                Some(fill) => fill.clone(),
+fn clip_line_to_rect(point: (f64, f64), dir: (f64, f64), rect: &Rect) -> Option<(f64, f64)> {
+    let mut t0 = f64::NEG_INFINITY;
+    let mut t1 = f64::INFINITY;
+    for &(p, d, lo, hi) in [
+        (point.0, dir.0, rect.min().x as f64, rect.max().x as f64),
+        (point.1, dir.1, rect.min().y as f64, rect.max().y as f64),
+    ]
+    .iter()
+    {
+        if d.abs() < 1e-12 {
+            if p < lo || p > hi {
+                return None;
+            }
+        } else {
+            let a = (lo - p) / d;
+            let b = (hi - p) / d;
+            t0 = t0.max(a.min(b));
+            t1 = t1.min(a.max(b));
+        }
+    }
+
+    if t0 < t1 {
+        Some((t0, t1))
+    } else {
+        None
+    }
+}
+
+fn end_piece(piece: &mut Vec<Coordinate>, lines: &mut MultiLineString) {
+    let piece = std::mem::take(piece);
+    if piece.len() > 1 {
+        lines.push(geo::LineString(piece));
+    }
+}
+
+// Cuts each line where it leaves the rect. Neighboring rects cut a shared
+// segment at the same point, so their pieces meet.
+fn clip_to_rect(lines: &MultiLineString, rect: &Rect) -> MultiLineString {
+    let mut clipped = MultiLineString::default();
+    for line in lines.0.iter() {
+        let mut piece: Vec<Coordinate> = Vec::new();
+        for w in line.0.windows(2) {
+            let a = (w[0].x as f64, w[0].y as f64);
+            let dir = ((w[1].x - w[0].x) as f64, (w[1].y - w[0].y) as f64);
+            let (t0, t1) = match clip_line_to_rect(a, dir, rect)
+                .map(|(t0, t1)| (t0.max(0.0), t1.min(1.0)))
+                .filter(|(t0, t1)| t0 < t1)
+            {
+                Some(span) => span,
                 None => {
-                    let fill = rotated_fill(&fill_func, angle, &bounds);
-                    cache.insert(approx_angle, fill.clone());
-                    fill
+                    end_piece(&mut piece, &mut clipped);
+                    continue;
                 }
             };
-            angle += rotate_angle;
-            Some(result)
+            let at = |t: f64| Coordinate {
+                x: (a.0 + dir.0 * t).round() as i64,
+                y: (a.1 + dir.1 * t).round() as i64,
+            };
+
+            if t0 > 0.0 {
+                end_piece(&mut piece, &mut clipped);
+            }
+            if piece.is_empty() {
+                piece.push(if t0 > 0.0 { at(t0) } else { w[0] });
+            }
+            piece.push(if t1 < 1.0 { at(t1) } else { w[1] });
+            if t1 < 1.0 {
+                end_piece(&mut piece, &mut clipped);
+            }
+        }
+        end_piece(&mut piece, &mut clipped);
+    }
+    clipped
+}
+
+// Parallel lines `spacing` apart for each normal angle, anchored at the origin
+pub fn anchored_lines_in_rect(spacing: i64, shift: f64, angles: &[f64], rect: &Rect) -> MultiLineString {
+    let spacing = spacing as f64;
+    let corners = [
+        (rect.min().x as f64, rect.min().y as f64),
+        (rect.max().x as f64, rect.min().y as f64),
+        (rect.max().x as f64, rect.max().y as f64),
+        (rect.min().x as f64, rect.max().y as f64),
+    ];
+
+    let mut lines = MultiLineString::default();
+    for angle in angles.iter() {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let offsets: Vec<f64> = corners.iter().map(|c| c.0 * cos + c.1 * sin).collect();
+        let lo = offsets.iter().cloned().fold(f64::INFINITY, f64::min);
+        let hi = offsets.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+        let first = ((lo - shift) / spacing).ceil() as i64;
+        let last = ((hi - shift) / spacing).floor() as i64;
+        for i in first..=last {
+            let offset = shift + i as f64 * spacing;
+            let point = (offset * cos, offset * sin);
+            let dir = (-sin, cos);
+            if let Some((t0, t1)) = clip_line_to_rect(point, dir, rect) {
+                lines.push(geo::LineString(vec![
+                    Coordinate {
+                        x: (point.0 + dir.0 * t0) as i64,
+                        y: (point.1 + dir.1 * t0) as i64,
+                    },
+                    Coordinate {
+                        x: (point.0 + dir.0 * t1) as i64,
+                        y: (point.1 + dir.1 * t1) as i64,
+                    },
+                ]));
+            }
+        }
+    }
+
+    lines
+}
+
+fn cubic_angles(initial_angle: f64) -> [f64; 3] {
+    [initial_angle, initial_angle + 120.0, initial_angle + 240.0]
+}
+
+// Cubes standing on a corner, cut by the layer plane
+pub fn cubic_fill_bounds(spacing: i64, initial_angle: f64, z: i64, bounds: Rect) -> MultiLineString {
+    let shift = z as f64 / 2.0_f64.sqrt();
+    anchored_lines_in_rect(spacing, shift, &cubic_angles(initial_angle), &bounds)
+}
+
+// Cubic fill sized by the octree leaves at this height
+pub fn adaptive_cubic_fill(
+    spacing: i64,
+    initial_angle: f64,
+    z: f64,
+    resolution: f64,
+    octree: &Octree,
+    bounds: Rect,
+) -> MultiLineString {
+    let shift = z / resolution / 2.0_f64.sqrt();
+    let angles = cubic_angles(initial_angle);
+
+    let mut lines = MultiLineString::default();
+    for leaf in octree.leaves.iter().filter(|leaf| leaf.contains_z(z)) {
+        let scale = (leaf.size / octree.min_size).round() as i64;
+        let min = Coordinate {
+            x: ((leaf.min[0] / resolution) as i64).max(bounds.min().x),
+            y: ((leaf.min[1] / resolution) as i64).max(bounds.min().y),
+        };
+        let max = Coordinate {
+            x: (((leaf.min[0] + leaf.size) / resolution) as i64).min(bounds.max().x),
+            y: (((leaf.min[1] + leaf.size) / resolution) as i64).min(bounds.max().y),
+        };
+        if min.x > max.x || min.y > max.y {
+            continue;
+        }
+        let rect = Rect::new(min, max);
+        lines
+            .0
+            .extend(anchored_lines_in_rect(spacing * scale, shift, &angles, &rect).0.into_iter());
+    }
+
+    lines
+}
+
+// Position of step `d` along a Hilbert curve filling a 2^order square
+fn hilbert_point(order: u32, d: u64) -> (i64, i64) {
+    let (mut x, mut y) = (0i64, 0i64);
+    let mut t = d;
+    let mut s = 1i64;
+    while s < (1i64 << order) {
+        let rx = ((t / 2) & 1) as i64;
+        let ry = ((t ^ rx as u64) & 1) as i64;
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
+
+pub fn hilbert_fill_bounds(spacing: i64, bounds: Rect) -> MultiLineString {
+    let side = bounds.width().max(bounds.height()) / spacing + 1;
+    let mut order = 0;
+    while (1i64 << order) < side {
+        order += 1;
+    }
+
+    let curve: LineString = (0..1u64 << (2 * order))
+        .map(|d| {
+            let (x, y) = hilbert_point(order, d);
+            Coordinate {
+                x: bounds.min().x + x * spacing,
+                y: bounds.min().y + y * spacing,
+            }
+        })
+        .collect::<Vec<Coordinate>>()
+        .into();
+
+    vec![curve].into()
+}
+
+// Archimedean spiral out from the center, in chords about `spacing` long
+pub fn archimedean_chords_fill_bounds(spacing: i64, bounds: Rect) -> MultiLineString {
+    let center = Coordinate {
+        x: (bounds.min().x + bounds.max().x) / 2,
+        y: (bounds.min().y + bounds.max().y) / 2,
+    };
+    let spacing = spacing as f64;
+    let max_radius = (bounds.width() as f64).hypot(bounds.height() as f64) / 2.0;
+    let growth = spacing / (2.0 * f64::consts::PI);
+
+    let mut points = Vec::<Coordinate>::new();
+    let mut theta = 0.0;
+    loop {
+        let radius = growth * theta;
+        points.push(Coordinate {
+            x: center.x + (radius * theta.cos()) as i64,
+            y: center.y + (radius * theta.sin()) as i64,
+        });
+        if radius > max_radius {
+            break;
         }
-    })
+        theta += (spacing / radius.max(spacing)).min(f64::consts::FRAC_PI_4);
+    }
+
+    let spiral: LineString = points.into();
+    vec![spiral].into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::OctreeCell;
+
+    #[test]
+    fn hilbert_first_order() {
+        let points: Vec<(i64, i64)> = (0..4).map(|d| hilbert_point(1, d)).collect();
+        assert_eq!(points, vec![(0, 0), (0, 1), (1, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn hilbert_visits_every_cell_in_unit_steps() {
+        let points: Vec<(i64, i64)> = (0..64).map(|d| hilbert_point(3, d)).collect();
+        let cells: BTreeSet<(i64, i64)> = points.iter().cloned().collect();
+        assert_eq!(cells.len(), 64);
+        assert!(cells.iter().all(|&(x, y)| (0..8).contains(&x) && (0..8).contains(&y)));
+        assert!(points
+            .windows(2)
+            .all(|w| (w[0].0 - w[1].0).abs() + (w[0].1 - w[1].1).abs() == 1));
+    }
+
+    #[test]
+    fn hilbert_fill_covers_bounds() {
+        let bounds = Rect::new(Coordinate { x: 100, y: 200 }, Coordinate { x: 160, y: 230 });
+        let fill = hilbert_fill_bounds(10, bounds);
+        let curve = &fill.0[0];
+        assert_eq!(curve.0[0], bounds.min());
+        let covered = curve.bounding_rect().unwrap();
+        assert!(covered.max().x >= bounds.max().x && covered.max().y >= bounds.max().y);
+    }
+
+    fn square_bounds(size: i64) -> Rect {
+        Rect::new(Coordinate { x: 0, y: 0 }, Coordinate { x: size, y: size })
+    }
+
+    // Positions of the lines running along Y, the first of the three angles
+    fn vertical_offsets(lines: &MultiLineString) -> Vec<i64> {
+        lines
+            .0
+            .iter()
+            .filter(|l| (l.0[0].x - l.0[l.0.len() - 1].x).abs() <= 1)
+            .map(|l| l.0[0].x)
+            .collect()
+    }
+
+    #[test]
+    fn cubic_lines_shift_with_z() {
+        let spacing = 1000;
+        for z in [0, 1000, 2000].iter() {
+            let shift = (*z as f64 / 2.0_f64.sqrt()) as i64;
+            let lines = cubic_fill_bounds(spacing, 0.0, *z, square_bounds(10_000));
+            let offsets = vertical_offsets(&lines);
+            assert!(!offsets.is_empty());
+            for x in offsets.iter() {
+                let phase = (x - shift).rem_euclid(spacing);
+                assert!(phase <= 1 || phase >= spacing - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn adaptive_cubic_widens_spacing_in_large_leaves() {
+        let bounds = square_bounds(16_000);
+        let leaf = |size: f64| Octree {
+            leaves: vec![OctreeCell { min: [0.0, 0.0, 0.0], size }],
+            min_size: 8_000.0,
+        };
+
+        // A leaf of the smallest size is plain cubic fill
+        let small = adaptive_cubic_fill(1000, 0.0, 1000.0, 1.0, &leaf(8_000.0), bounds);
+        let clipped = Rect::new(Coordinate { x: 0, y: 0 }, Coordinate { x: 8_000, y: 8_000 });
+        assert_eq!(small, cubic_fill_bounds(1000, 0.0, 1000, clipped));
+
+        let large = adaptive_cubic_fill(1000, 0.0, 1000.0, 1.0, &leaf(16_000.0), bounds);
+        let offsets = vertical_offsets(&large);
+        assert!(offsets.windows(2).all(|w| (w[1] - w[0]).abs() >= 1999));
+    }
+
+    fn rect(x0: i64, y0: i64, x1: i64, y1: i64) -> Rect {
+        Rect::new(Coordinate { x: x0, y: y0 }, Coordinate { x: x1, y: y1 })
+    }
+
+    #[test]
+    fn gyroid_stays_inside_bounds() {
+        let bounds = rect(-123_456_789, 10_000_000, 98_765_432, 210_000_000);
+        let fill = gyroid_fill_bounds(12.0, 1.3, 1e-7, bounds);
+        assert!(!fill.0.is_empty());
+        assert!(fill.0.iter().flat_map(|l| l.0.iter()).all(|c| {
+            c.x >= bounds.min().x
+                && c.x <= bounds.max().x
+                && c.y >= bounds.min().y
+                && c.y <= bounds.max().y
+        }));
+    }
+
+    #[test]
+    fn gyroid_meets_across_shared_edges() {
+        let edge = 123_456_789;
+        let ends_on_edge = |fill: MultiLineString| {
+            let mut ends: Vec<(i64, i64)> = fill
+                .0
+                .iter()
+                .flat_map(|l| vec![l.0[0], l.0[l.0.len() - 1]])
+                .filter(|c| (c.x - edge).abs() <= 1)
+                .map(|c| (c.x, c.y))
+                .collect();
+            ends.sort_unstable();
+            ends
+        };
+
+        let left = ends_on_edge(gyroid_fill_bounds(12.0, 1.3, 1e-7, rect(0, 0, edge, 200_000_000)));
+        let right = ends_on_edge(gyroid_fill_bounds(
+            12.0,
+            1.3,
+            1e-7,
+            rect(edge, 0, 2 * edge, 200_000_000),
+        ));
+        assert!(!left.is_empty());
+        assert_eq!(left, right);
+    }
+
+    fn segment_length(a: &Coordinate, b: &Coordinate) -> f64 {
+        ((b.x - a.x) as f64).hypot((b.y - a.y) as f64)
+    }
+
+    #[test]
+    fn honeycomb_walls_are_regular_hexagons() {
+        let cell_size = 30_000_000;
+        let side = cell_size as f64 / 3.0_f64.sqrt();
+        let fill = honeycomb_fill_bounds(cell_size, 0, rect(0, 0, 100_000_000, 100_000_000));
+
+        // Every edge, upright or slanted, is one side long
+        for line in fill.0.iter() {
+            for w in line.0.windows(2) {
+                assert!((segment_length(&w[0], &w[1]) - side).abs() < 2.0);
+            }
+        }
+        // Pairs of columns share their upright walls on even rows, the first
+        // row below the bounds is odd
+        for pair in fill.0.chunks(2) {
+            for (a, b) in pair[0].0.iter().zip(pair[1].0.iter()).skip(2).step_by(4) {
+                assert_eq!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn honeycomb_offset_pushes_shared_walls_apart() {
+        let bounds = rect(0, 0, 100_000_000, 100_000_000);
+        let gap = |offset: i64| {
+            let fill = honeycomb_fill_bounds(30_000_000, offset, bounds);
+            let (a, b) = (&fill.0[0].0, &fill.0[1].0);
+            // The second row is a shared wall
+            b[2].x - a[2].x
+        };
+        assert_eq!(gap(0), 0);
+        assert!((gap(2_000_000) - 4_000_000).abs() <= 1);
+        assert!((gap(-2_000_000) + 4_000_000).abs() <= 1);
+    }
 }
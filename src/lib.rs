@@ -7,6 +7,7 @@ extern crate geo_collate;
 extern crate geo_svg;
 extern crate hedge;
 extern crate quickersort;
+extern crate rand;
 extern crate rayon;
 extern crate regex;
 extern crate rstar;
@@ -18,6 +19,7 @@ extern crate svg;
 #[macro_use]
 extern crate lazy_static;
 
+mod bridge;
 mod captures;
 pub mod config;
 mod connect;
@@ -25,10 +27,15 @@ mod error;
 mod generator;
 mod id_factory;
 mod infill;
+mod lightning;
 mod mesh;
 mod model_file;
 mod ops;
+pub mod pattern;
+mod plate;
 mod slicer;
+mod spiral;
+mod transform;
 mod types;
 mod writers;
 pub mod run;
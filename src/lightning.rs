@@ -0,0 +1,222 @@
+use geo::prelude::*;
+use geo_clipper::*;
+
+use crate::config::*;
+use crate::types::*;
+
+type FPoint = (f64, f64);
+
+fn dist(a: FPoint, b: FPoint) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+fn nearest_on_segment(p: FPoint, a: FPoint, b: FPoint) -> FPoint {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len_sq = dx * dx + dy * dy;
+    if len_sq == 0.0 {
+        return a;
+    }
+    let t = (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len_sq).max(0.0).min(1.0);
+    (a.0 + dx * t, a.1 + dy * t)
+}
+
+// Region outlines are backed by walls or solid fill, so branches end there
+fn nearest_on_boundary(p: FPoint, boundary: &Vec<(FPoint, FPoint)>) -> Option<FPoint> {
+    boundary
+        .iter()
+        .map(|(a, b)| nearest_on_segment(p, *a, *b))
+        .min_by(|a, b| dist(p, *a).partial_cmp(&dist(p, *b)).unwrap())
+}
+
+fn boundary_segments(regions: &SparseRegions) -> Vec<(FPoint, FPoint)> {
+    regions
+        .0
+        .iter()
+        .map(|region| region.clone().into_line_strings())
+        .flatten()
+        .map(|ring| {
+            ring.lines()
+                .map(|l| {
+                    (
+                        (l.start.x as f64, l.start.y as f64),
+                        (l.end.x as f64, l.end.y as f64),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .flatten()
+        .collect()
+}
+
+fn to_point(p: FPoint) -> Coordinate {
+    Coordinate {
+        x: p.0 as i64,
+        y: p.1 as i64,
+    }
+}
+
+// Lightning infill, grown from the top of the part down. Wherever a top skin
+// rests on sparse area, branch tips are seeded `spacing` apart. Each tip
+// attaches to whichever is closer, the region outline or a tip that is itself
+// closer to the outline, and that tree is printed. On every layer below, tips
+// move towards what they attach to by at most the distance the branch angle
+// allows, merging into each other and vanishing into the outline, so only
+// what holds up the skins gets printed.
+pub fn lightning_fill(
+    config: &Config,
+    top_exposed: &Vec<MultiPolygon>,
+    sparse: &Vec<SparseRegions>,
+) -> Vec<MultiLineString> {
+    let spacing = config.sparse_fill_line_spacing_dsc();
+    let skin_layers = config.num_top_layers();
+    let max_move = config.discretized(config.layer_height) as f64
+        * config.lightning_angle.to_radians().tan();
+    let merge_dist = config.nozzle_diameter_dsc() as f64 / 2.0;
+
+    let mut tips: Vec<FPoint> = Vec::new();
+    let mut layers: Vec<MultiLineString> = Vec::with_capacity(sparse.len());
+
+    for i in (0..sparse.len()).rev() {
+        let regions = &sparse[i];
+        let region_polys: MultiPolygon = regions.0.iter().map(|r| r.poly.clone()).collect();
+        let boundary = boundary_segments(regions);
+
+        tips.retain(|p| region_polys.contains(&to_point(*p)));
+
+        if let Some(skin) = top_exposed.get(i + skin_layers) {
+            let demand = skin.intersection(regions);
+            if let Some(rect) = demand.bounding_rect() {
+                let mut y = rect.min().y.div_euclid(spacing) * spacing;
+                while y <= rect.max().y {
+                    let mut x = rect.min().x.div_euclid(spacing) * spacing;
+                    while x <= rect.max().x {
+                        let p = (x as f64, y as f64);
+                        if demand.contains(&to_point(p))
+                            && tips.iter().all(|t| dist(*t, p) >= spacing as f64)
+                        {
+                            tips.push(p);
+                        }
+                        x += spacing;
+                    }
+                    y += spacing;
+                }
+            }
+        }
+
+        let boundary_dist: Vec<Option<(FPoint, f64)>> = tips
+            .iter()
+            .map(|p| nearest_on_boundary(*p, &boundary).map(|b| (b, dist(*p, b))))
+            .collect();
+
+        let mut lines = MultiLineString::default();
+        let mut targets: Vec<Option<(FPoint, bool)>> = Vec::with_capacity(tips.len());
+        for (t, tip) in tips.iter().enumerate() {
+            let own_dist = boundary_dist[t].map(|(_, d)| d).unwrap_or(f64::INFINITY);
+            let parent = tips
+                .iter()
+                .enumerate()
+                .filter(|(o, _)| {
+                    *o != t
+                        && boundary_dist[*o].map(|(_, d)| d).unwrap_or(f64::INFINITY) < own_dist
+                })
+                .map(|(_, other)| *other)
+                .min_by(|a, b| dist(*tip, *a).partial_cmp(&dist(*tip, *b)).unwrap());
+
+            let target = match (parent, boundary_dist[t]) {
+                (Some(parent), Some((_, d))) if dist(*tip, parent) < d => Some((parent, false)),
+                (_, Some((edge, _))) => Some((edge, true)),
+                (Some(parent), None) => Some((parent, false)),
+                (None, None) => None,
+            };
+
+            if let Some((target, _)) = target {
+                lines.push(geo::LineString(vec![to_point(*tip), to_point(target)]));
+            }
+            targets.push(target);
+        }
+        layers.push(lines);
+
+        let mut moved: Vec<FPoint> = Vec::with_capacity(tips.len());
+        for (tip, target) in tips.iter().zip(targets.into_iter()) {
+            let (target, is_edge) = match target {
+                Some(target) => target,
+                None => continue,
+            };
+            let d = dist(*tip, target);
+            if d <= max_move {
+                if is_edge {
+                    continue;
+                }
+                moved.push(target);
+            } else {
+                let f = max_move / d;
+                moved.push((tip.0 + (target.0 - tip.0) * f, tip.1 + (target.1 - tip.1) * f));
+            }
+        }
+
+        tips.clear();
+        for p in moved {
+            if tips.iter().all(|t| dist(*t, p) > merge_dist) {
+                tips.push(p);
+            }
+        }
+    }
+
+    layers.reverse();
+    layers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: i64 = 200_000_000;
+
+    fn square() -> MultiPolygon {
+        let ring: LineString = vec![(0, 0), (SIZE, 0), (SIZE, SIZE), (0, SIZE), (0, 0)].into();
+        MultiPolygon(vec![Polygon::new(ring, Vec::new())])
+    }
+
+    // Deepest branch tip, measured from the outline
+    fn depth(lines: &MultiLineString) -> i64 {
+        lines
+            .0
+            .iter()
+            .map(|l| {
+                let tip = l.0[0];
+                tip.x.min(tip.y).min(SIZE - tip.x).min(SIZE - tip.y)
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn branches_grow_down_towards_the_outline() {
+        let config = Config::default_test();
+        let num_layers = 12 + config.num_top_layers();
+        let sparse: Vec<SparseRegions> = vec![square().into(); num_layers];
+        let mut top_exposed = vec![MultiPolygon::default(); num_layers];
+        top_exposed[num_layers - 1] = square();
+
+        let fill = lightning_fill(&config, &top_exposed, &sparse);
+        let seeded = num_layers - 1 - config.num_top_layers();
+        assert!(fill[seeded + 1..].iter().all(|lines| lines.0.is_empty()));
+        assert!(!fill[seeded].0.is_empty());
+        assert!(!fill[0].0.is_empty());
+
+        let max_move = config.discretized(config.layer_height) as f64
+            * config.lightning_angle.to_radians().tan();
+        assert!(depth(&fill[0]) as f64 <= depth(&fill[seeded]) as f64 - max_move);
+    }
+
+    #[test]
+    fn no_skin_above_no_fill() {
+        let config = Config::default_test();
+        let sparse: Vec<SparseRegions> = vec![square().into(); 10];
+        let top_exposed = vec![MultiPolygon::default(); 10];
+
+        let fill = lightning_fill(&config, &top_exposed, &sparse);
+        assert_eq!(fill.len(), 10);
+        assert!(fill.iter().all(|lines| lines.0.is_empty()));
+    }
+}
@@ -3,6 +3,8 @@ use hedge::Mesh;
 use std::f64;
 use hedge::Face;
 
+use crate::model_file::data::FreeSurface;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Range {
     pub min: f64,
@@ -20,6 +22,13 @@ impl Range {
     pub fn len(&self) -> f64 {
         self.max - self.min
     }
+
+    pub fn union(&self, other: &Range) -> Range {
+        Range {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -29,6 +38,16 @@ pub struct Bounds3D {
     pub z: Range,
 }
 
+impl Bounds3D {
+    pub fn union(&self, other: &Bounds3D) -> Bounds3D {
+        Bounds3D {
+            x: self.x.union(&other.x),
+            y: self.y.union(&other.y),
+            z: self.z.union(&other.z),
+        }
+    }
+}
+
 pub fn mesh_bounds(mesh: &hedge::Mesh) -> Bounds3D {
     let mut bounds = Bounds3D {
         x: Range::new(),
@@ -61,6 +80,24 @@ pub fn mesh_bounds(mesh: &hedge::Mesh) -> Bounds3D {
     bounds
 }
 
+pub fn surface_bounds(surface: &FreeSurface) -> Bounds3D {
+    let mut bounds = Bounds3D {
+        x: Range::new(),
+        y: Range::new(),
+        z: Range::new(),
+    };
+    for point in surface.iter().flatten() {
+        bounds.x.min = bounds.x.min.min(point[0]);
+        bounds.x.max = bounds.x.max.max(point[0]);
+        bounds.y.min = bounds.y.min.min(point[1]);
+        bounds.y.max = bounds.y.max.max(point[1]);
+        bounds.z.min = bounds.z.min.min(point[2]);
+        bounds.z.max = bounds.z.max.max(point[2]);
+    }
+
+    bounds
+}
+
 pub fn z_range(mesh: &Mesh, face: &Face) -> Range {
     let mut max = f64::NEG_INFINITY;
     let mut min = f64::INFINITY;
@@ -1,3 +1,5 @@
 pub mod bounds;
+pub mod octree;
 
 pub use self::bounds::*;
+pub use self::octree::*;
@@ -0,0 +1,113 @@
+use crate::mesh::bounds::*;
+use crate::model_file::data::*;
+
+#[derive(Debug, Clone, Copy)]
+pub struct OctreeCell {
+    pub min: Vertex,
+    pub size: f64,
+}
+
+pub struct Octree {
+    pub leaves: Vec<OctreeCell>,
+    pub min_size: f64,
+}
+
+impl OctreeCell {
+    fn children(&self) -> Vec<OctreeCell> {
+        let half = self.size / 2.0;
+        (0..8)
+            .map(|i| OctreeCell {
+                min: [
+                    self.min[0] + if i & 1 != 0 { half } else { 0.0 },
+                    self.min[1] + if i & 2 != 0 { half } else { 0.0 },
+                    self.min[2] + if i & 4 != 0 { half } else { 0.0 },
+                ],
+                size: half,
+            })
+            .collect()
+    }
+
+    pub fn contains_z(&self, z: f64) -> bool {
+        z >= self.min[2] && z < self.min[2] + self.size
+    }
+
+    // Conservative test for any point of the triangle being within `distance`
+    // of the cell, on bounding boxes
+    fn near(&self, triangle: &FreeTriangle, distance: f64) -> bool {
+        (0..3).all(|axis| {
+            let lo = triangle.iter().map(|v| v[axis]).fold(f64::INFINITY, f64::min);
+            let hi = triangle.iter().map(|v| v[axis]).fold(f64::NEG_INFINITY, f64::max);
+            hi >= self.min[axis] - distance && lo <= self.min[axis] + self.size + distance
+        })
+    }
+}
+
+fn subdivide(
+    cell: OctreeCell,
+    surface: &FreeSurface,
+    candidates: Vec<usize>,
+    min_size: f64,
+    leaves: &mut Vec<OctreeCell>,
+) {
+    let near: Vec<usize> = candidates
+        .into_iter()
+        .filter(|i| cell.near(&surface[*i], cell.size))
+        .collect();
+
+    if near.is_empty() || cell.size / 2.0 < min_size {
+        leaves.push(cell);
+        return;
+    }
+
+    for child in cell.children() {
+        subdivide(child, surface, near.clone(), min_size, leaves);
+    }
+}
+
+// Cells are split while the surface is within a cell size of them, so they
+// are `min_size` next to the walls and top and bottom skins and grow
+// towards the middle of the part
+pub fn adaptive_octree(surface: &FreeSurface, bounds: &Bounds3D, min_size: f64) -> Octree {
+    let extent = bounds.x.len().max(bounds.y.len()).max(bounds.z.len());
+    let mut size = min_size;
+    while size < extent {
+        size *= 2.0;
+    }
+
+    let root = OctreeCell {
+        min: [bounds.x.min, bounds.y.min, bounds.z.min],
+        size,
+    };
+
+    let mut leaves = Vec::new();
+    subdivide(root, surface, (0..surface.len()).collect(), min_size, &mut leaves);
+
+    Octree { leaves, min_size }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_shrink_towards_the_surface() {
+        // A 16mm floor under a 16mm cube of space
+        let surface: FreeSurface = vec![
+            [[0.0, 0.0, 0.0], [16.0, 0.0, 0.0], [16.0, 16.0, 0.0]],
+            [[0.0, 0.0, 0.0], [16.0, 16.0, 0.0], [0.0, 16.0, 0.0]],
+        ];
+        let range = Range { min: 0.0, max: 16.0 };
+        let bounds = Bounds3D { x: range, y: range, z: range };
+        let octree = adaptive_octree(&surface, &bounds, 1.0);
+
+        let volume: f64 = octree.leaves.iter().map(|l| l.size.powi(3)).sum();
+        assert_eq!(volume, 16.0f64.powi(3));
+        for leaf in octree.leaves.iter() {
+            if leaf.min[2] == 0.0 {
+                assert_eq!(leaf.size, 1.0);
+            } else if leaf.min[2] >= 8.0 {
+                assert!(leaf.size >= 4.0);
+            }
+        }
+    }
+}
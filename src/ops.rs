@@ -1,9 +1,18 @@
 use crate::captures::*;
 use crate::config::*;
 use crate::infill::*;
+use crate::mesh::Octree;
 use crate::types::*;
+use crate::geometry::medial_axis::*;
+use crate::geometry::polygon_utils::*;
+use geo::prelude::BoundingRect;
 use geo::MultiLineString;
 use geo_clipper::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use rstar::primitives::GeomWithData;
+use rstar::RTree;
 use std::iter;
 use simple_generator::Generator;
 use simple_generator::*;
@@ -37,6 +46,47 @@ pub fn offset_op(delta: f64) -> impl Fn(MultiPolygon) -> MultiPolygon {
     move |p: MultiPolygon| p.offset(delta, JoinType::Miter(3.0), EndType::ClosedPolygon)
 }
 
+pub fn compensation_op(config: &Config) -> impl Fn((usize, MultiPolygon)) -> MultiPolygon {
+    let xy = config.discretized(config.xy_size_compensation) as f64;
+    let hole = config.discretized(config.hole_compensation) as f64;
+    let elephant_foot = config.discretized(config.elephant_foot_compensation) as f64;
+
+    move |(layer, outline): (usize, MultiPolygon)| {
+        let mut outline = outline;
+        if xy != 0.0 {
+            outline = outline.offset(xy, JoinType::Miter(3.0), EndType::ClosedPolygon);
+        }
+        if hole != 0.0 {
+            // Per polygon, so islands inside holes are left alone
+            outline = outline
+                .0
+                .into_iter()
+                .map(|poly| {
+                    let (exterior, interiors) = poly.into_inner();
+                    let solid = MultiPolygon(vec![Polygon::new(exterior, Vec::new())])
+                        .union(&MultiPolygon::default());
+                    let holes = MultiPolygon(
+                        interiors
+                            .into_iter()
+                            .map(|ring| Polygon::new(ring, Vec::new()))
+                            .collect(),
+                    )
+                    .union(&MultiPolygon::default())
+                    .offset(hole, JoinType::Miter(3.0), EndType::ClosedPolygon);
+                    solid.difference(&holes).0.into_iter()
+                })
+                .flatten()
+                .collect::<MultiPolygon>()
+                .union(&MultiPolygon::default());
+        }
+        if layer == 0 && elephant_foot != 0.0 {
+            outline =
+                outline.offset(-elephant_foot, JoinType::Miter(3.0), EndType::ClosedPolygon);
+        }
+        outline
+    }
+}
+
 pub fn shells_op(config: &Config) -> impl Fn(&LayerRegions) -> LayerShells {
     let num_shells = config.num_shells;
     let initial = -config.nozzle_diameter_dsc() / 2;
@@ -46,6 +96,7 @@ pub fn shells_op(config: &Config) -> impl Fn(&LayerRegions) -> LayerShells {
         let mut layer_shells = Vec::new();
         for region in layer_regions.0.iter() {
             let mut region_shells = Vec::new();
+            let mut region_holes = Vec::new();
 
             for i in 0..num_shells {
                 let offset_dist = initial - i as i64 * per_shell;
@@ -55,15 +106,21 @@ pub fn shells_op(config: &Config) -> impl Fn(&LayerRegions) -> LayerShells {
                     EndType::ClosedPolygon,
                 );
                 let mut rank_shells = Vec::new();
+                let mut rank_holes = Vec::new();
                 for shell_poly in shell_polys {
                     let (exterior, interior) = shell_poly.into_inner();
+                    rank_holes.push(false);
+                    rank_holes.extend(interior.iter().map(|_| true));
                     rank_shells.extend(iter::once(exterior).chain(interior.into_iter()));
                 }
                 region_shells.push(MultiLineString(rank_shells));
+                region_holes.push(rank_holes);
             }
 
             layer_shells.push(Shells {
                 shells: region_shells,
+                holes: region_holes,
+                widths: Vec::new(),
                 region_id: region.id,
             });
         }
@@ -72,6 +129,283 @@ pub fn shells_op(config: &Config) -> impl Fn(&LayerRegions) -> LayerShells {
     }
 }
 
+// Per region area left between the shells and the interior, less thin walls
+pub fn wall_gaps_op(config: &Config) -> impl Fn(&LayerRegions) -> Vec<MultiPolygon> {
+    let thin_walls = config.thin_walls;
+    let num_shells = config.num_shells;
+    let width = config.nozzle_diameter_dsc() as f64;
+    let initial = -config.nozzle_diameter_dsc() / 2;
+    let per_shell = config.nozzle_diameter_dsc() - config.shell_overlap_dsc();
+    let interior_offset = config.interior_offset_dsc() as f64;
+
+    move |layer_regions: &LayerRegions| {
+        layer_regions
+            .0
+            .iter()
+            .map(|region| {
+                let region: MultiPolygon = vec![region.poly.clone()].into();
+                let mut covered =
+                    region.offset(interior_offset, JoinType::Miter(3.0), EndType::ClosedPolygon);
+                for i in 0..num_shells {
+                    let center = region.offset(
+                        (initial - i as i64 * per_shell) as f64,
+                        JoinType::Miter(3.0),
+                        EndType::ClosedPolygon,
+                    );
+                    let band = center
+                        .offset(width / 2.0, JoinType::Miter(3.0), EndType::ClosedPolygon)
+                        .difference(&center.offset(
+                            -width / 2.0,
+                            JoinType::Miter(3.0),
+                            EndType::ClosedPolygon,
+                        ));
+                    covered = covered.union(&band);
+                }
+                let gaps = region.difference(&covered);
+                if thin_walls {
+                    gaps.difference(&thin_area(&region, width))
+                } else {
+                    gaps
+                }
+            })
+            .collect()
+    }
+}
+
+// Splits edges longer than `step` so the width can change along them
+fn subdivide(ring: &LineString, step: f64) -> LineString {
+    let mut points = Vec::with_capacity(ring.0.len());
+    for w in ring.0.windows(2) {
+        let (dx, dy) = ((w[1].x - w[0].x) as f64, (w[1].y - w[0].y) as f64);
+        let count = (dx.hypot(dy) / step).ceil().max(1.0) as usize;
+        points.extend((0..count).map(|i| Coordinate {
+            x: w[0].x + (dx * i as f64 / count as f64) as i64,
+            y: w[0].y + (dy * i as f64 / count as f64) as i64,
+        }));
+    }
+    points.extend(ring.0.last().cloned());
+    points.into()
+}
+
+// Fixed width shells with the gaps between them shared out. The walls between
+// each gap and the outline widen by an equal share of half the gap and spread
+// apart to match, so the beads from both sides meet in the middle.
+pub fn variable_width_shells_op(config: &Config) -> impl Fn(&LayerRegions) -> LayerShells {
+    let shells = shells_op(config);
+    let gaps = wall_gaps_op(config);
+    let width = config.nozzle_diameter_dsc() as f64;
+    let per_shell = (config.nozzle_diameter_dsc() - config.shell_overlap_dsc()) as f64;
+    let num_shells = config.num_shells as f64;
+
+    move |layer_regions: &LayerRegions| {
+        LayerShells(
+            shells(layer_regions)
+                .0
+                .into_iter()
+                .zip(gaps(layer_regions).iter())
+                .map(|(mut region_shells, region_gaps)| {
+                    let centers = RTree::bulk_load(
+                        region_gaps
+                            .0
+                            .iter()
+                            .map(|gap| center_samples(gap, width / 4.0, width * 2.0))
+                            .flatten()
+                            .map(|(p, w)| GeomWithData::new([p.0, p.1], w))
+                            .collect(),
+                    );
+
+                    let bead = |rank: f64, point: &mut Coordinate| -> i64 {
+                        let p = [point.x as f64, point.y as f64];
+                        let center = match centers.nearest_neighbor(&p) {
+                            Some(center) => center,
+                            None => return width as i64,
+                        };
+                        let (dx, dy) = (center.geom()[0] - p[0], center.geom()[1] - p[1]);
+                        let distance = dx.hypot(dy);
+                        // Walls from this one in to the gap, if it's this side's gap
+                        let to_gap = distance - center.data / 2.0 - width / 2.0;
+                        let inner = (to_gap / per_shell).round();
+                        let num_walls = rank + inner + 1.0;
+                        if distance == 0.0 || inner < 0.0 || num_walls > num_shells {
+                            return width as i64;
+                        }
+
+                        let extra = (center.data / 2.0 / num_walls).min(width);
+                        let shift = (rank + 0.5) * extra / distance;
+                        point.x += (dx * shift) as i64;
+                        point.y += (dy * shift) as i64;
+                        (width + extra) as i64
+                    };
+
+                    region_shells.widths = region_shells
+                        .shells
+                        .iter_mut()
+                        .enumerate()
+                        .map(|(rank, rings)| {
+                            rings
+                                .0
+                                .iter_mut()
+                                .map(|ring| {
+                                    *ring = subdivide(ring, width);
+                                    ring.0.iter_mut().map(|p| bead(rank as f64, p)).collect()
+                                })
+                                .collect()
+                        })
+                        .collect();
+                    region_shells
+                })
+                .collect(),
+        )
+    }
+}
+
+// Runs of a path where it's at least the minimum width
+fn split_by_width(path: LineString, widths: Vec<i64>, min_width: i64) -> Vec<LineString> {
+    let mut runs = Vec::new();
+    let mut run = Vec::new();
+    for (point, width) in path.0.into_iter().zip(widths.into_iter()) {
+        if width >= min_width {
+            run.push(point);
+        } else if !run.is_empty() {
+            runs.push(std::mem::take(&mut run));
+        }
+    }
+    runs.push(run);
+    runs.into_iter().filter(|run| run.len() >= 2).map(|run| run.into()).collect()
+}
+
+// Open paths down the middle of the gaps fixed width shells leave
+pub fn gap_fill_op(config: &Config) -> impl Fn(&LayerRegions) -> MultiLineString<i64> {
+    let gaps = wall_gaps_op(config);
+    let width = config.nozzle_diameter_dsc() as f64;
+    let min_width = config.gap_fill_min_width_dsc();
+
+    move |layer_regions: &LayerRegions| {
+        gaps(layer_regions)
+            .iter()
+            .map(|g| g.0.iter())
+            .flatten()
+            .map(|gap| medial_axis(gap, width / 2.0, width))
+            .flatten()
+            .map(|(path, widths)| split_by_width(path, widths, min_width))
+            .flatten()
+            .filter(|path| path_length(path) >= width / 2.0)
+            .collect()
+    }
+}
+
+// Parts of a region the first shell can't reach
+fn thin_area(region: &MultiPolygon, nozzle: f64) -> MultiPolygon {
+    let opened = region
+        .offset(-nozzle / 2.0, JoinType::Miter(3.0), EndType::ClosedPolygon)
+        .offset(nozzle / 2.0, JoinType::Miter(3.0), EndType::ClosedPolygon);
+    region.difference(&opened)
+}
+
+pub fn thin_walls_op(config: &Config) -> impl Fn(&LayerRegions) -> Vec<ThinWall> {
+    let nozzle = config.nozzle_diameter_dsc() as f64;
+
+    move |layer_regions: &LayerRegions| {
+        let mut walls = Vec::new();
+        for region in layer_regions.0.iter() {
+            let region: MultiPolygon = vec![region.poly.clone()].into();
+            for thin in thin_area(&region, nozzle).0.iter() {
+                walls.extend(
+                    medial_axis(thin, nozzle / 2.0, nozzle * 2.0)
+                        .into_iter()
+                        .filter(|(path, _)| path_length(path) >= nozzle)
+                        .map(|(path, widths)| ThinWall { path, widths }),
+                );
+            }
+        }
+        walls
+    }
+}
+
+fn fuzz_ring(
+    ring: &LineString,
+    widths: &[i64],
+    thickness: f64,
+    spacing: f64,
+    rng: &mut StdRng,
+) -> (LineString, Vec<i64>) {
+    if spacing <= 0.0 {
+        return (ring.clone(), widths.to_vec());
+    }
+    let mut points = Vec::new();
+    let mut point_widths = Vec::new();
+    let mut next = rng.gen_range(0.5..1.0) * spacing;
+    for (i, w) in ring.0.windows(2).enumerate() {
+        let (a, b) = (w[0], w[1]);
+        let (dx, dy) = ((b.x - a.x) as f64, (b.y - a.y) as f64);
+        let length = dx.hypot(dy);
+        if length == 0.0 {
+            continue;
+        }
+        let normal = (-dy / length, dx / length);
+        while next <= length {
+            let along = next;
+            let offset = rng.gen_range(-0.5..0.5) * thickness;
+            points.push(Coordinate {
+                x: (a.x as f64 + dx * along / length + normal.0 * offset) as i64,
+                y: (a.y as f64 + dy * along / length + normal.1 * offset) as i64,
+            });
+            if !widths.is_empty() {
+                let t = along / length;
+                point_widths.push((widths[i] as f64 * (1.0 - t) + widths[i + 1] as f64 * t) as i64);
+            }
+            next += rng.gen_range(0.5..1.0) * spacing;
+        }
+        next -= length;
+    }
+
+    // Too short to fuzz, keep it as it was
+    if points.len() < 3 {
+        return (ring.clone(), widths.to_vec());
+    }
+    points.push(points[0]);
+    if !point_widths.is_empty() {
+        point_widths.push(point_widths[0]);
+    }
+    (points.into(), point_widths)
+}
+
+// Seeded per layer, so the jitter is the same every run
+pub fn fuzzy_skin_op(config: &Config) -> impl Fn((usize, LayerShells)) -> LayerShells {
+    let thickness = config.discretized(config.fuzzy_skin_thickness) as f64;
+    let spacing = config.discretized(config.fuzzy_skin_point_distance) as f64;
+    let seed = config.fuzzy_skin_seed;
+    let skip_holes = config.fuzzy_skin_skip_holes;
+
+    move |(layer, layer_shells): (usize, LayerShells)| {
+        let mut rng = StdRng::seed_from_u64(seed.wrapping_add(layer as u64));
+        LayerShells(
+            layer_shells
+                .0
+                .into_iter()
+                .map(|mut shells| {
+                    if let Some(outer) = shells.shells.first_mut() {
+                        let holes = shells.holes[0].iter();
+                        for (i, (ring, hole)) in outer.0.iter_mut().zip(holes).enumerate() {
+                            if skip_holes && *hole {
+                                continue;
+                            }
+                            let widths = shells.widths.first().map_or(&[][..], |w| &w[i][..]);
+                            let (fuzzed, fuzzed_widths) =
+                                fuzz_ring(ring, widths, thickness, spacing, &mut rng);
+                            *ring = fuzzed;
+                            if let Some(outer_widths) = shells.widths.first_mut() {
+                                outer_widths[i] = fuzzed_widths;
+                            }
+                        }
+                    }
+                    shells
+                })
+                .collect(),
+        )
+    }
+}
+
 pub fn interiors_op(config: &Config) -> impl Fn(&LayerRegions) -> InteriorRegions {
     let delta = config.interior_offset_dsc() as f64;
 
@@ -82,13 +416,40 @@ pub fn interiors_op(config: &Config) -> impl Fn(&LayerRegions) -> InteriorRegion
     }
 }
 
+// Loops inward from half a line inside the region until nothing is left
+pub fn concentric_fill_op<Tag: RegionTag>(
+    spacing: i64,
+) -> impl Fn(&TaggedRegions<Tag>) -> MultiLineString<i64> {
+    move |regions: &TaggedRegions<Tag>| {
+        let mut loops = Vec::new();
+        for region in regions.0.iter() {
+            let mut current = region.poly.offset(
+                -(spacing / 2) as f64,
+                JoinType::Miter(3.0),
+                EndType::ClosedPolygon,
+            );
+            while !current.0.is_empty() {
+                for poly in current.0.iter() {
+                    let (exterior, interior) = poly.clone().into_inner();
+                    loops.extend(iter::once(exterior).chain(interior.into_iter()));
+                }
+                current =
+                    current.offset(-spacing as f64, JoinType::Miter(3.0), EndType::ClosedPolygon);
+            }
+        }
+        MultiLineString(loops)
+    }
+}
+
+// Top skins from the layers above and bottom skins from the layers below
 pub fn solid_grouping_gen<'a>(
     config: &Config,
     top: impl Iterator<Item = &'a MultiPolygon> + Clone + Send,
     bottom: impl Iterator<Item = &'a MultiPolygon> + Clone + Send,
 ) -> impl Generator<Item = MultiPolygon> + Captures<'a> {
     let num_top_layers = config.num_top_layers();
-    let num_bottom_layers = config.num_top_layers();
+    let num_bottom_layers = config.num_bottom_layers();
+    let min_skin_area = config.min_skin_area_dsc();
 
     GeneratorFunc::new(move || {
         let mut cur_top = top.clone();
@@ -97,69 +458,413 @@ pub fn solid_grouping_gen<'a>(
         let mut top_accum = VecDeque::<&'a MultiPolygon>::with_capacity(num_top_layers);
         let mut bottom_accum = VecDeque::<&'a MultiPolygon>::with_capacity(num_bottom_layers);
 
-        for _ in 0..(num_top_layers - 1) {
+        for _ in 0..num_top_layers.saturating_sub(1) {
             match cur_top.next() {
                 Some(next_top) => top_accum.push_back(next_top),
                 None => break,
             }
         }
 
+        let mut first = true;
         move || {
-            match cur_top.next() {
-                Some(next_top) => {
+            // The window of top skins shrinks once it runs past the last layer
+            if !first {
+                top_accum.pop_front();
+            }
+            first = false;
+            if num_top_layers > 0 {
+                if let Some(next_top) = cur_top.next() {
                     top_accum.push_back(next_top);
-                    if top_accum.len() > num_top_layers {
-                        top_accum.pop_front();
-                    }
                 }
-                None => {}
             }
 
-            match cur_bottom.next() {
-                Some(next_bottom) => {
-                    bottom_accum.push_back(next_bottom);
-                    if bottom_accum.len() > num_bottom_layers {
-                        bottom_accum.pop_front();
-                    }
-
-                    Some(
-                        top_accum
-                            .iter()
-                            .chain(bottom_accum.iter())
-                            .map(|v| v.0.iter())
-                            .flatten()
-                            .map(|p| p.clone())
-                            .collect(),
-                    )
+            let next_bottom = cur_bottom.next()?;
+            if num_bottom_layers > 0 {
+                bottom_accum.push_back(next_bottom);
+                if bottom_accum.len() > num_bottom_layers {
+                    bottom_accum.pop_front();
                 }
-                None => None,
             }
+
+            Some(
+                top_accum
+                    .iter()
+                    .chain(bottom_accum.iter())
+                    .map(|v| v.0.iter())
+                    .flatten()
+                    .filter(|p| p.area_f64() >= min_skin_area)
+                    .map(|p| p.clone())
+                    .collect(),
+            )
         }
     })
 }
 
-pub fn solid_fill_overlay_gen<'a>(
-    config: &Config,
-    bounds: Rect,
-) -> impl Generator<Item = MultiLineString<i64>> + 'a {
-    let spacing = config.solid_fill_line_spacing_dsc();
-    rotating_fill_gen(
-        move |r: Rect| linear_fill_bounds(spacing, r),
-        config.solid_fill_initial_angle,
-        config.solid_fill_angle_increment,
-        bounds,
+pub fn solid_fill_bounds(config: &Config, layer: usize, bounds: Rect) -> MultiLineString<i64> {
+    anchored_lines_in_rect(
+        config.solid_fill_line_spacing_dsc(),
+        0.0,
+        &[config.solid_fill_angle(layer)],
+        &bounds,
     )
 }
 
-pub fn sparse_fill_overlay_gen<'a>(
+pub fn top_fill_overlay(config: &Config, bounds: Rect) -> MultiLineString<i64> {
+    let spacing = config.solid_fill_line_spacing_dsc();
+    match config.top_fill_pattern {
+        TopFillPattern::HilbertCurve => hilbert_fill_bounds(spacing, bounds),
+        TopFillPattern::ArchimedeanChords => archimedean_chords_fill_bounds(spacing, bounds),
+        TopFillPattern::Solid => MultiLineString::default(),
+    }
+}
+
+pub fn sparse_fill_bounds(
     config: &Config,
+    layer: usize,
+    z: f64,
+    octree: Option<&Octree>,
     bounds: Rect,
-) -> impl Generator<Item = MultiLineString<i64>> + 'a {
+) -> MultiLineString<i64> {
     let spacing = config.sparse_fill_line_spacing_dsc();
-    rotating_fill_gen(
-        move |r: Rect| linear_fill_bounds(spacing, r),
-        config.sparse_fill_initial_angle,
-        config.sparse_fill_angle_increment,
-        bounds,
-    )
+    let resolution = config.resolution;
+    let initial_angle = config.sparse_fill_initial_angle;
+    let angle = initial_angle + config.sparse_fill_angle_increment * layer as f64;
+
+    match config.sparse_fill_pattern {
+        FillPattern::Linear => anchored_lines_in_rect(spacing, 0.0, &[angle], &bounds),
+        FillPattern::Grid => anchored_lines_in_rect(spacing, 0.0, &[angle, angle + 90.0], &bounds),
+        FillPattern::Triangles => anchored_lines_in_rect(
+            spacing,
+            0.0,
+            &[angle, angle + 60.0, angle + 120.0],
+            &bounds,
+        ),
+        FillPattern::Gyroid => gyroid_fill_bounds(config.gyroid_period(), z, resolution, bounds),
+        // Cells can't rotate between layers, only the wall offset alternates
+        FillPattern::Honeycomb => {
+            let wall_offset = config.nozzle_diameter_dsc() / 2;
+            let offset = if layer % 2 == 0 { wall_offset } else { -wall_offset };
+            let cell_size = config.honeycomb_cell_size_dsc();
+            anchored_rotated_fill(
+                &|r: Rect| honeycomb_fill_bounds(cell_size, offset, r),
+                initial_angle,
+                &bounds,
+            )
+        }
+        FillPattern::Cubic => {
+            cubic_fill_bounds(spacing, initial_angle, (z / resolution) as i64, bounds)
+        }
+        FillPattern::AdaptiveCubic => match octree {
+            Some(octree) => {
+                adaptive_cubic_fill(spacing, initial_angle, z, resolution, octree, bounds)
+            }
+            None => cubic_fill_bounds(spacing, initial_angle, (z / resolution) as i64, bounds),
+        },
+        FillPattern::Lightning | FillPattern::Concentric => MultiLineString::default(),
+    }
+}
+
+// `fill` over each region's bounds, clipped to the region
+pub fn fill_regions<Tag: RegionTag + Sync>(
+    layers: &Vec<TaggedRegions<Tag>>,
+    fill: impl Fn(usize, Rect) -> MultiLineString<i64> + Sync,
+) -> Vec<MultiLineString<i64>> {
+    layers
+        .par_iter()
+        .enumerate()
+        .map(|(layer, regions)| {
+            regions
+                .0
+                .par_iter()
+                .filter_map(|region| {
+                    let bounds = region.poly.bounding_rect()?;
+                    let area: MultiPolygon = vec![region.poly.clone()].into();
+                    Some(fill(layer, bounds).intersection(&area))
+                })
+                .reduce(MultiLineString::default, |mut a, b| {
+                    a.0.extend(b.0.into_iter());
+                    a
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counter clockwise square ring in millimeters
+    fn square(min: i64, max: i64) -> LineString {
+        let (min, max) = (min * 10_000_000, max * 10_000_000);
+        vec![
+            Coordinate { x: min, y: min },
+            Coordinate { x: max, y: min },
+            Coordinate { x: max, y: max },
+            Coordinate { x: min, y: max },
+            Coordinate { x: min, y: min },
+        ]
+        .into()
+    }
+
+    fn mm2(area: f64) -> f64 {
+        area * 1e14
+    }
+
+    #[test]
+    fn hole_compensation_keeps_islands_in_holes() {
+        let mut config = Config::default_test();
+        config.hole_compensation = 1.0;
+
+        let mut hole = square(20, 80);
+        hole.0.reverse();
+        let ring = Polygon::new(square(0, 100), vec![hole]);
+        let island = Polygon::new(square(40, 60), Vec::new());
+
+        let compensated = compensation_op(&config)((1, MultiPolygon(vec![ring, island])));
+
+        assert_eq!(compensated.0.len(), 2);
+        let mut areas: Vec<f64> = compensated.0.iter().map(|p| p.area_f64()).collect();
+        areas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert!((areas[0] - mm2(400.0)).abs() < mm2(1.0));
+        assert!((areas[1] - mm2(10000.0 - 62.0 * 62.0)).abs() < mm2(1.0));
+    }
+
+    fn square_layer(min: i64, max: i64) -> MultiPolygon {
+        MultiPolygon(vec![Polygon::new(square(min, max), Vec::new())])
+    }
+
+    fn total_area(polygons: &MultiPolygon) -> f64 {
+        polygons.0.iter().map(|p| p.area_f64()).sum()
+    }
+
+    #[test]
+    fn xy_compensation_grows_outlines() {
+        let mut config = Config::default_test();
+        config.xy_size_compensation = 0.5;
+
+        let compensated = compensation_op(&config)((3, square_layer(0, 10)));
+        assert!((total_area(&compensated) - mm2(121.0)).abs() < mm2(0.01));
+    }
+
+    #[test]
+    fn elephant_foot_only_on_first_layer() {
+        let mut config = Config::default_test();
+        config.elephant_foot_compensation = 0.5;
+        let compensate = compensation_op(&config);
+
+        let first = compensate((0, square_layer(0, 10)));
+        let second = compensate((1, square_layer(0, 10)));
+        assert!((total_area(&first) - mm2(81.0)).abs() < mm2(0.01));
+        assert!((total_area(&second) - mm2(100.0)).abs() < mm2(0.01));
+    }
+
+    fn skin_config(top_thickness: f64, bottom_thickness: f64) -> Config {
+        let mut config = Config::default_test();
+        config.layer_height = 0.25;
+        config.top_thickness = top_thickness;
+        config.bottom_thickness = bottom_thickness;
+        config.min_skin_area = 0.0;
+        config
+    }
+
+    #[test]
+    fn solid_grouping_spreads_top_down_and_bottom_up() {
+        let config = skin_config(0.75, 0.5);
+        let mut top = vec![MultiPolygon::default(); 6];
+        let mut bottom = vec![MultiPolygon::default(); 6];
+        top[4] = square_layer(0, 10);
+        bottom[1] = square_layer(20, 30);
+
+        let counts: Vec<usize> = solid_grouping_gen(&config, top.iter(), bottom.iter())
+            .into_iter()
+            .map(|layer| layer.0.len())
+            .collect();
+        assert_eq!(counts, vec![0, 1, 2, 1, 1, 0]);
+    }
+
+    #[test]
+    fn solid_grouping_without_top_layers() {
+        let config = skin_config(0.0, 0.5);
+        let top = vec![square_layer(0, 10); 3];
+        let bottom = vec![square_layer(20, 30), MultiPolygon::default(), MultiPolygon::default()];
+
+        let counts: Vec<usize> = solid_grouping_gen(&config, top.iter(), bottom.iter())
+            .into_iter()
+            .map(|layer| layer.0.len())
+            .collect();
+        assert_eq!(counts, vec![1, 1, 0]);
+    }
+
+    #[test]
+    fn solid_grouping_drops_small_skins() {
+        let mut config = skin_config(0.25, 0.25);
+        config.min_skin_area = 50.0;
+        let top = vec![MultiPolygon(vec![
+            Polygon::new(square(0, 5), Vec::new()),
+            Polygon::new(square(20, 30), Vec::new()),
+        ])];
+        let bottom = vec![MultiPolygon::default()];
+
+        let skins: Vec<MultiPolygon> =
+            solid_grouping_gen(&config, top.iter(), bottom.iter()).into_iter().collect();
+        assert_eq!(skins.len(), 1);
+        assert_eq!(skins[0].0.len(), 1);
+        assert!((total_area(&skins[0]) - mm2(100.0)).abs() < mm2(0.01));
+    }
+
+    // Closed counter clockwise rectangle from the origin, in millimeters
+    fn rect_region(width: f64, height: f64) -> LayerRegions {
+        let (w, h) = ((width * 1e7) as i64, (height * 1e7) as i64);
+        let ring: LineString = vec![(0, 0), (w, 0), (w, h), (0, h), (0, 0)].into();
+        MultiPolygon(vec![Polygon::new(ring, Vec::new())]).into()
+    }
+
+    #[test]
+    fn variable_width_walls_fill_narrow_regions() {
+        let mut config = Config::default_test();
+        config.wall_generator = WallGenerator::VariableWidth;
+
+        // Room for one wall a side, plus 0.3mm between them
+        let layer_shells = variable_width_shells_op(&config)(&rect_region(10.0, 1.1));
+        let shells = &layer_shells.0[0];
+        assert_eq!(shells.widths.len(), shells.shells.len());
+        let outer = &shells.shells[0].0[0];
+        let widths = &shells.widths[0][0];
+        assert_eq!(outer.0.len(), widths.len());
+
+        let middle: Vec<(&Coordinate, &i64)> = outer
+            .0
+            .iter()
+            .zip(widths.iter())
+            .filter(|(p, _)| p.x > 20_000_000 && p.x < 80_000_000)
+            .collect();
+        assert!(!middle.is_empty());
+        for (point, width) in middle {
+            let from_edge = point.y.min(11_000_000 - point.y);
+            assert!((*width - 5_500_000).abs() < 100_000, "width {}", width);
+            assert!((from_edge - 2_750_000).abs() < 100_000, "{} from the edge", from_edge);
+        }
+    }
+
+    #[test]
+    fn variable_width_walls_keep_the_nozzle_width_with_room() {
+        let mut config = Config::default_test();
+        config.wall_generator = WallGenerator::VariableWidth;
+
+        let layer_shells = variable_width_shells_op(&config)(&rect_region(10.0, 10.0));
+        let shells = &layer_shells.0[0];
+        assert_eq!(shells.shells.len(), config.num_shells as usize);
+        for (rings, widths) in shells.shells.iter().zip(shells.widths.iter()) {
+            for (ring, ring_widths) in rings.0.iter().zip(widths.iter()) {
+                assert_eq!(ring.0.len(), ring_widths.len());
+                let nozzle = config.nozzle_diameter_dsc();
+                assert!(ring_widths.iter().all(|w| (*w - nozzle).abs() < 1_000));
+            }
+        }
+    }
+
+    #[test]
+    fn gap_fill_drops_narrow_runs() {
+        let path: LineString = (0..6)
+            .map(|x| Coordinate { x, y: 0 })
+            .collect::<Vec<Coordinate>>()
+            .into();
+        let runs = split_by_width(path, vec![5, 5, 1, 5, 5, 5], 3);
+
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].0.len(), 2);
+        assert_eq!(runs[1].0.len(), 3);
+        assert_eq!(runs[1].0[0].x, 3);
+    }
+
+    #[test]
+    fn thin_walls_only_where_shells_dont_fit() {
+        let config = Config::default_test();
+
+        assert!(thin_walls_op(&config)(&rect_region(10.0, 10.0)).is_empty());
+
+        let walls = thin_walls_op(&config)(&rect_region(10.0, 0.3));
+        assert_eq!(walls.len(), 1);
+        assert!(walls[0].widths.iter().all(|w| (w - 3_000_000).abs() < 1_000));
+    }
+
+    // 20mm square with a 10mm hole
+    fn framed_region() -> LayerRegions {
+        let mut hole = square(5, 15);
+        hole.0.reverse();
+        MultiPolygon(vec![Polygon::new(square(0, 20), vec![hole])]).into()
+    }
+
+    fn fuzzed(config: &Config) -> (LayerShells, LayerShells) {
+        let shells = shells_op(config)(&framed_region());
+        let fuzzed = fuzzy_skin_op(config)((0, shells_op(config)(&framed_region())));
+        (shells, fuzzed)
+    }
+
+    #[test]
+    fn fuzzy_skin_is_seeded() {
+        let mut config = Config::default_test();
+        let (shells, first) = fuzzed(&config);
+        let (_, second) = fuzzed(&config);
+        assert!(first.0[0].shells[0] != shells.0[0].shells[0]);
+        assert!(first.0[0].shells[0] == second.0[0].shells[0]);
+
+        config.fuzzy_skin_seed += 1;
+        let (_, reseeded) = fuzzed(&config);
+        assert!(first.0[0].shells[0] != reseeded.0[0].shells[0]);
+    }
+
+    #[test]
+    fn fuzzy_skin_leaves_inner_walls_and_skipped_holes() {
+        let mut config = Config::default_test();
+        config.fuzzy_skin_skip_holes = true;
+        let (shells, fuzzed) = fuzzed(&config);
+        let (before, after) = (&shells.0[0], &fuzzed.0[0]);
+
+        assert!(before.holes[0].contains(&true));
+        for (i, hole) in before.holes[0].iter().enumerate() {
+            assert_eq!(*hole, before.shells[0].0[i] == after.shells[0].0[i]);
+        }
+        assert!(before.shells.len() > 1);
+        assert!(before.shells[1..] == after.shells[1..]);
+    }
+
+    #[test]
+    fn honeycomb_offset_alternates_between_layers() {
+        let mut config = Config::default_test();
+        config.sparse_fill_pattern = FillPattern::Honeycomb;
+        let bounds = square_layer(0, 20).bounding_rect().unwrap();
+        let layer = |layer: usize| sparse_fill_bounds(&config, layer, 0.0, None, bounds);
+        let with_offset = |offset: i64| {
+            let cell_size = config.honeycomb_cell_size_dsc();
+            anchored_rotated_fill(
+                &|r: Rect| honeycomb_fill_bounds(cell_size, offset, r),
+                config.sparse_fill_initial_angle,
+                &bounds,
+            )
+        };
+
+        let wall_offset = config.nozzle_diameter_dsc() / 2;
+        assert!(layer(0) == with_offset(wall_offset));
+        assert!(layer(1) == with_offset(-wall_offset));
+        assert!(layer(0) != layer(1));
+        assert!(layer(2) == layer(0));
+    }
+
+    #[test]
+    fn concentric_loops_step_inward_until_used_up() {
+        let spacing = 10_000_000;
+        let regions: LayerRegions = square_layer(0, 10).into();
+        let loops = concentric_fill_op(spacing)(&regions);
+
+        // Half a line in, then a line apart, the last a 1mm square
+        assert_eq!(loops.0.len(), 5);
+        for (i, ring) in loops.0.iter().enumerate() {
+            let inset = spacing / 2 + i as i64 * spacing;
+            let bounds = ring.bounding_rect().unwrap();
+            assert!((bounds.min().x - inset).abs() <= 1);
+            assert!((bounds.max().y - (100_000_000 - inset)).abs() <= 1);
+            assert_eq!(ring.0.first(), ring.0.last());
+        }
+    }
 }
@@ -0,0 +1,182 @@
+use crate::config::Config;
+use crate::error::*;
+use crate::infill::*;
+use crate::types::FillPattern;
+use geo::prelude::BoundingRect;
+use geo_clipper::*;
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+pub use crate::types::{Coordinate, LineString, MultiLineString, MultiPolygon, Polygon, Rect};
+
+// Everything a pattern gets to see when filling one layer. Coordinates are
+// discretized by `config.resolution`, and `bounds` covers every layer of the
+// object so patterns can stay aligned from layer to layer.
+pub struct FillContext<'a> {
+    pub layer: usize,
+    pub z: f64,
+    pub region: &'a MultiPolygon,
+    pub bounds: Rect,
+    pub config: &'a Config,
+}
+
+pub trait InfillPattern: Send + Sync {
+    // Fill lines for the region, already clipped to it
+    fn fill(&self, context: &FillContext) -> MultiLineString;
+}
+
+pub struct LinearPattern;
+
+impl InfillPattern for LinearPattern {
+    fn fill(&self, context: &FillContext) -> MultiLineString {
+        let config = context.config;
+        let spacing = config.sparse_fill_line_spacing_dsc();
+        let angle = config.sparse_fill_initial_angle
+            + config.sparse_fill_angle_increment * context.layer as f64;
+        let bounds = match context.region.bounding_rect() {
+            Some(bounds) => bounds,
+            None => return MultiLineString::default(),
+        };
+        anchored_lines_in_rect(spacing, 0.0, &[angle], &bounds).intersection(context.region)
+    }
+}
+
+// Fills every polygon of every layer separately, so patterns only cover the
+// parts being filled instead of a box around the whole layer
+pub fn fill_regions(
+    pattern: &dyn InfillPattern,
+    layers: &[MultiPolygon],
+    layer_z: &[f64],
+    bounds: Rect,
+    config: &Config,
+) -> Vec<MultiLineString> {
+    layers
+        .par_iter()
+        .zip(layer_z.par_iter())
+        .enumerate()
+        .map(|(layer, (polygons, z))| {
+            polygons
+                .0
+                .par_iter()
+                .map(|polygon| {
+                    pattern.fill(&FillContext {
+                        layer,
+                        z: *z,
+                        region: &vec![polygon.clone()].into(),
+                        bounds,
+                        config,
+                    })
+                })
+                .reduce(MultiLineString::default, |mut a, b| {
+                    a.0.extend(b.0.into_iter());
+                    a
+                })
+        })
+        .collect()
+}
+
+// Named sparse fill patterns. Built in patterns that have been ported to
+// `InfillPattern` are registered by default, and downstream crates add their
+// own before calling `run_with_patterns`.
+pub struct PatternRegistry {
+    patterns: HashMap<String, Box<dyn InfillPattern>>,
+}
+
+impl PatternRegistry {
+    pub fn new() -> Self {
+        PatternRegistry {
+            patterns: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, name: &str, pattern: impl InfillPattern + 'static) {
+        self.patterns.insert(name.to_string(), Box::new(pattern));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn InfillPattern> {
+        self.patterns.get(name).map(|p| p.as_ref())
+    }
+
+    // The configured custom pattern, or the registered one standing in for a
+    // ported built in pattern. `None` leaves the built in fill to do it.
+    pub fn for_config(&self, config: &Config) -> NarsilResult<Option<&dyn InfillPattern>> {
+        match (&config.custom_fill_pattern, config.sparse_fill_pattern) {
+            (Some(name), _) => self
+                .get(name)
+                .map(Some)
+                .ok_or_else(|| NarsilError::UnknownPattern(name.clone())),
+            (None, FillPattern::Linear) => Ok(self.get("linear")),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl Default for PatternRegistry {
+    fn default() -> Self {
+        let mut registry = PatternRegistry::new();
+        registry.register("linear", LinearPattern);
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The diagonal of whatever region it's given, unclipped
+    struct DiagonalPattern;
+
+    impl InfillPattern for DiagonalPattern {
+        fn fill(&self, context: &FillContext) -> MultiLineString {
+            match context.region.bounding_rect() {
+                Some(r) => vec![LineString::from(vec![r.min(), r.max()])].into(),
+                None => MultiLineString::default(),
+            }
+        }
+    }
+
+    fn square(x: i64, size: i64) -> Polygon {
+        let ring: LineString =
+            vec![(x, 0), (x + size, 0), (x + size, size), (x, size), (x, 0)].into();
+        Polygon::new(ring, Vec::new())
+    }
+
+    #[test]
+    fn configured_pattern_is_looked_up() {
+        let mut config = Config::default_test();
+        let mut registry = PatternRegistry::default();
+        registry.register("diagonal", DiagonalPattern);
+
+        config.custom_fill_pattern = Some("diagonal".to_string());
+        let region: MultiPolygon = vec![square(0, 10)].into();
+        let context = FillContext {
+            layer: 0,
+            z: 0.0,
+            region: &region,
+            bounds: region.bounding_rect().unwrap(),
+            config: &config,
+        };
+        let fill = registry.for_config(&config).unwrap().unwrap().fill(&context);
+        assert_eq!(fill.0.len(), 1);
+        assert_eq!(fill.0[0].0, vec![Coordinate { x: 0, y: 0 }, Coordinate { x: 10, y: 10 }]);
+
+        config.custom_fill_pattern = Some("missing".to_string());
+        assert!(matches!(
+            registry.for_config(&config),
+            Err(NarsilError::UnknownPattern(ref name)) if name == "missing"
+        ));
+    }
+
+    #[test]
+    fn fill_regions_fills_each_polygon_on_its_own() {
+        let config = Config::default_test();
+        let layer: MultiPolygon = vec![square(0, 10), square(100, 10)].into();
+        let bounds = layer.bounding_rect().unwrap();
+
+        let fill = fill_regions(&DiagonalPattern, &[layer], &[0.0], bounds, &config);
+        let mut diagonals: Vec<(i64, i64)> =
+            fill[0].0.iter().map(|l| (l.0[0].x, l.0[1].x)).collect();
+        diagonals.sort_unstable();
+        assert_eq!(diagonals, vec![(0, 10), (100, 110)]);
+    }
+}
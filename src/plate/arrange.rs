@@ -0,0 +1,207 @@
+use geo::prelude::*;
+use std::f64;
+
+use crate::config::*;
+use crate::plate::error::*;
+use crate::plate::object::*;
+use crate::transform::*;
+
+struct Placement {
+    footprint: geo::Polygon<f64>,
+    rect: geo::Rect<f64>,
+}
+
+fn fits(candidate: &Placement, placed: &Vec<Placement>, spacing: f64, printer: &Printer) -> bool {
+    if !printer.bed_contains(&candidate.footprint)
+        || printer.excluded_by(&candidate.footprint).is_some()
+    {
+        return false;
+    }
+
+    placed.iter().all(|other| {
+        candidate.rect.max().x + spacing <= other.rect.min().x
+            || other.rect.max().x + spacing <= candidate.rect.min().x
+            || candidate.rect.max().y + spacing <= other.rect.min().y
+            || other.rect.max().y + spacing <= candidate.rect.min().y
+            || candidate.footprint.euclidean_distance(&other.footprint) >= spacing
+    })
+}
+
+// Bottom-left packing of the object footprints. Candidate positions are the
+// bed corner and the far edges of everything placed so far, and the lowest,
+// leftmost one that keeps `spacing` to every placed footprint wins. Objects
+// with an explicit position are moved there and packed around.
+pub fn arrange(objects: &mut Vec<PlateObject>, config: &Config) -> PlateResult<()> {
+    if objects.is_empty() {
+        return Err(PlateError::NoObjects);
+    }
+
+    let bed = config.printer.bed_rect();
+    let spacing = if config.sequential {
+        config
+            .arrange_spacing
+            .max(config.printer.extruder_clearance_radius)
+    } else {
+        config.arrange_spacing
+    };
+
+    let mut placed = Vec::<Placement>::new();
+
+    for object in objects.iter_mut() {
+        if let Some(position) = object.position {
+            let rect = object.footprint().bounding_rect().unwrap();
+            let center = rect.center();
+            object.transform(&Transform::translation(
+                position[0] - center.x,
+                position[1] - center.y,
+                0.0,
+            ));
+            let footprint = object.footprint();
+            placed.push(Placement {
+                rect: footprint.bounding_rect().unwrap(),
+                footprint,
+            });
+        }
+    }
+    let num_fixed = placed.len();
+
+    let footprints: Vec<geo::Polygon<f64>> = objects.iter().map(|o| o.footprint()).collect();
+    let mut order: Vec<usize> = (0..objects.len())
+        .filter(|i| objects[*i].position.is_none())
+        .collect();
+    order.sort_by(|a, b| {
+        footprints[*b]
+            .unsigned_area()
+            .partial_cmp(&footprints[*a].unsigned_area())
+            .unwrap()
+    });
+
+    let mut offsets = vec![(0.0, 0.0); objects.len()];
+
+    for index in order.iter().cloned() {
+        let footprint = &footprints[index];
+        let rect = footprint.bounding_rect().unwrap();
+
+        let xs: Vec<f64> = std::iter::once(bed.min().x)
+            .chain(placed.iter().map(|p| p.rect.max().x + spacing))
+            .collect();
+        let ys: Vec<f64> = std::iter::once(bed.min().y)
+            .chain(placed.iter().map(|p| p.rect.max().y + spacing))
+            .collect();
+
+        let mut best: Option<(f64, f64)> = None;
+        for y in ys.iter() {
+            for x in xs.iter() {
+                if let Some((best_x, best_y)) = best {
+                    if (*y, *x) >= (best_y, best_x) {
+                        continue;
+                    }
+                }
+
+                let dx = x - rect.min().x;
+                let dy = y - rect.min().y;
+                let candidate = Placement {
+                    footprint: footprint.translate(dx, dy),
+                    rect: rect.translate(dx, dy),
+                };
+
+                if fits(&candidate, &placed, spacing, &config.printer) {
+                    best = Some((*x, *y));
+                }
+            }
+        }
+
+        let (x, y) = best.ok_or(PlateError::DoesNotFit(objects[index].name.clone()))?;
+        let dx = x - rect.min().x;
+        let dy = y - rect.min().y;
+        placed.push(Placement {
+            footprint: footprint.translate(dx, dy),
+            rect: rect.translate(dx, dy),
+        });
+        offsets[index] = (dx, dy);
+    }
+
+    // Center the packed group on the bed, unless it has to fit around fixed
+    // objects or exclusion zones
+    let (center_x, center_y) = if num_fixed == 0 && config.printer.exclusion_zones.is_empty() {
+        let packed = placed.iter().skip(1).fold(placed[0].rect, |accum, p| {
+            geo::Rect::new(
+                geo::Coordinate {
+                    x: accum.min().x.min(p.rect.min().x),
+                    y: accum.min().y.min(p.rect.min().y),
+                },
+                geo::Coordinate {
+                    x: accum.max().x.max(p.rect.max().x),
+                    y: accum.max().y.max(p.rect.max().y),
+                },
+            )
+        });
+        (
+            bed.min().x + (bed.width() - packed.width()) / 2.0 - packed.min().x,
+            bed.min().y + (bed.height() - packed.height()) / 2.0 - packed.min().y,
+        )
+    } else {
+        (0.0, 0.0)
+    };
+
+    for index in order {
+        let (dx, dy) = offsets[index];
+        objects[index].transform(&Transform::translation(dx + center_x, dy + center_y, 0.0));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::surface_bounds;
+
+    fn cube(name: &str, size: f64) -> PlateObject {
+        PlateObject::test_box(name, [0.0, 0.0, 0.0], [size, size, size])
+    }
+
+    #[test]
+    fn arranged_objects_keep_their_spacing() {
+        let config = Config::default_test();
+        let mut objects = vec![cube("a", 30.0), cube("b", 20.0), cube("c", 20.0)];
+        arrange(&mut objects, &config).unwrap();
+
+        let footprints: Vec<geo::Polygon<f64>> = objects.iter().map(|o| o.footprint()).collect();
+        for (i, a) in footprints.iter().enumerate() {
+            assert!(config.printer.bed_contains(a));
+            for b in footprints.iter().skip(i + 1) {
+                assert!(a.euclidean_distance(b) >= config.arrange_spacing - 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_objects_stay_put() {
+        let config = Config::default_test();
+        let mut fixed = cube("fixed", 20.0);
+        fixed.position = Some([100.0, 100.0]);
+        let mut objects = vec![fixed, cube("free", 20.0)];
+        arrange(&mut objects, &config).unwrap();
+
+        let bounds = surface_bounds(&objects[0].surface);
+        assert!((bounds.x.min - 90.0).abs() < 1e-9 && (bounds.y.min - 90.0).abs() < 1e-9);
+        assert!(objects[0].footprint().euclidean_distance(&objects[1].footprint()) >= 5.0 - 1e-9);
+    }
+
+    #[test]
+    fn oversized_objects_do_not_fit() {
+        let config = Config::default_test();
+        let mut objects = vec![cube("huge", 250.0)];
+        assert!(matches!(
+            arrange(&mut objects, &config),
+            Err(PlateError::DoesNotFit(ref name)) if name == "huge"
+        ));
+    }
+
+    #[test]
+    fn empty_plate() {
+        let config = Config::default_test();
+        assert!(matches!(arrange(&mut Vec::new(), &config), Err(PlateError::NoObjects)));
+    }
+}
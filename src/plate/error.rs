@@ -0,0 +1,33 @@
+use std::error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum PlateError {
+    NoObjects,
+    DoesNotFit(String),
+    TooTallForGantry(String, f64, f64),
+    ExtruderCollision(String, String),
+}
+
+impl error::Error for PlateError {}
+
+impl fmt::Display for PlateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoObjects => write!(f, "Plate has no objects"),
+            Self::DoesNotFit(name) => write!(f, "Object {} does not fit on the bed", name),
+            Self::TooTallForGantry(name, height, gantry) => write!(
+                f,
+                "Object {} is {:.2} tall, only the last object printed may exceed the gantry height of {:.2}",
+                name, height, gantry
+            ),
+            Self::ExtruderCollision(a, b) => write!(
+                f,
+                "Objects {} and {} are closer than the extruder clearance radius",
+                a, b
+            ),
+        }
+    }
+}
+
+pub type PlateResult<T> = Result<T, PlateError>;
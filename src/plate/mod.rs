@@ -0,0 +1,11 @@
+pub mod arrange;
+pub mod error;
+pub mod object;
+pub mod sequential;
+pub mod validate;
+
+pub use self::arrange::*;
+pub use self::error::*;
+pub use self::object::*;
+pub use self::sequential::*;
+pub use self::validate::*;
@@ -0,0 +1,151 @@
+use geo::ConvexHull;
+use std::fs::File;
+use std::path::Path as filePath;
+
+use crate::config::*;
+use crate::error::*;
+use crate::model_file;
+use crate::model_file::data::*;
+use crate::transform::*;
+
+pub struct PlateObject {
+    pub name: String,
+    pub surface: FreeSurface,
+    pub overrides: ConfigOverrides,
+    pub position: Option<[f64; 2]>,
+    pub auto_orient: bool,
+}
+
+fn load_surface(filename: &str) -> NarsilResult<FreeSurface> {
+    let mut fh = File::open(filename)?;
+    let ft = model_file::identify(&mut fh)?;
+    Ok(model_file::load_triangles(&ft, &mut fh)?)
+}
+
+impl PlateObject {
+    pub fn load(description: &ObjectDescription) -> NarsilResult<Vec<PlateObject>> {
+        let surface = load_surface(description.file.as_str())?;
+        Ok(Self::instances(description, &surface))
+    }
+
+    // Every instance of the description, named `name#n` when there's more
+    // than one
+    fn instances(description: &ObjectDescription, surface: &FreeSurface) -> Vec<PlateObject> {
+        let name = filePath::new(description.file.as_str())
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let scale = description.transform.scale;
+        let mut transform = Transform::scale(scale[0], scale[1], scale[2]);
+        if let Some(rotate) = description.transform.rotate {
+            transform = transform
+                .then(&Transform::axis_angle(&[1.0, 0.0, 0.0], rotate[0]))
+                .then(&Transform::axis_angle(&[0.0, 1.0, 0.0], rotate[1]))
+                .then(&Transform::axis_angle(&[0.0, 0.0, 1.0], rotate[2]));
+        }
+        let surface = transform_surface(surface, &transform);
+
+        (0..description.instances)
+            .map(|instance| PlateObject {
+                name: if description.instances > 1 {
+                    format!("{}#{}", name, instance + 1)
+                } else {
+                    name.clone()
+                },
+                surface: surface.clone(),
+                overrides: description.overrides.clone(),
+                // Copies of a placed object would stack, so only the first keeps the position
+                position: if instance == 0 {
+                    description.transform.translate
+                } else {
+                    None
+                },
+                auto_orient: description.transform.rotate.is_none(),
+            })
+            .collect()
+    }
+
+    pub fn transform(&mut self, transform: &Transform) {
+        self.surface = transform_surface(&self.surface, transform);
+    }
+
+    // Convex hull of the surface projected onto the bed
+    pub fn footprint(&self) -> geo::Polygon<f64> {
+        let points: geo::MultiPoint<f64> = self
+            .surface
+            .iter()
+            .flatten()
+            .map(|v| geo::Point::new(v[0], v[1]))
+            .collect();
+        points.convex_hull()
+    }
+}
+
+#[cfg(test)]
+impl PlateObject {
+    // Axis aligned box from `min` to `max`
+    pub fn test_box(name: &str, min: Vertex, max: Vertex) -> PlateObject {
+        let corner = |i: usize| -> Vertex {
+            [
+                if i & 1 == 0 { min[0] } else { max[0] },
+                if i & 2 == 0 { min[1] } else { max[1] },
+                if i & 4 == 0 { min[2] } else { max[2] },
+            ]
+        };
+        let quads = [
+            [0, 2, 3, 1],
+            [4, 5, 7, 6],
+            [0, 1, 5, 4],
+            [2, 6, 7, 3],
+            [0, 4, 6, 2],
+            [1, 3, 7, 5],
+        ];
+        PlateObject {
+            name: name.to_string(),
+            surface: quads
+                .iter()
+                .map(|q| {
+                    vec![
+                        [corner(q[0]), corner(q[1]), corner(q[2])],
+                        [corner(q[0]), corner(q[2]), corner(q[3])],
+                    ]
+                })
+                .flatten()
+                .collect(),
+            overrides: ConfigOverrides::default(),
+            position: None,
+            auto_orient: false,
+        }
+    }
+
+    // 20mm square block standing at `x`, `y`
+    pub fn test_block(name: &str, x: f64, y: f64, height: f64) -> PlateObject {
+        PlateObject::test_box(name, [x, y, 0.0], [x + 20.0, y + 20.0, height])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instances_are_numbered() {
+        let surface = PlateObject::test_box("part", [0.0; 3], [10.0; 3]).surface;
+        let mut description = ObjectDescription::new("parts/bracket.stl".to_string());
+        description.transform.translate = Some([20.0, 30.0]);
+
+        let single = PlateObject::instances(&description, &surface);
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].name, "bracket.stl");
+
+        description.instances = 3;
+        let objects = PlateObject::instances(&description, &surface);
+        let names: Vec<&str> = objects.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names, vec!["bracket.stl#1", "bracket.stl#2", "bracket.stl#3"]);
+        assert_eq!(objects[0].position, Some([20.0, 30.0]));
+        assert!(objects[1..].iter().all(|o| o.position.is_none()));
+    }
+}
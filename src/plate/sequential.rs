@@ -0,0 +1,97 @@
+use geo::prelude::*;
+
+use crate::config::*;
+use crate::mesh::*;
+use crate::plate::error::*;
+use crate::plate::object::*;
+
+// Print order for one-at-a-time printing. Objects go front to back, with the
+// one allowed to be taller than the gantry last. Once an object is done, the
+// print head can't come within the clearance radius of it again, and
+// nothing but the last object may reach up into the gantry.
+pub fn sequence(objects: &Vec<PlateObject>, printer: &Printer) -> PlateResult<Vec<usize>> {
+    let footprints: Vec<geo::Polygon<f64>> = objects.iter().map(|o| o.footprint()).collect();
+    let bounds: Vec<Bounds3D> = objects.iter().map(|o| surface_bounds(&o.surface)).collect();
+
+    for i in 0..objects.len() {
+        for j in (i + 1)..objects.len() {
+            let distance = footprints[i].euclidean_distance(&footprints[j]);
+            if distance < printer.extruder_clearance_radius {
+                return Err(PlateError::ExtruderCollision(
+                    objects[i].name.clone(),
+                    objects[j].name.clone(),
+                ));
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..objects.len()).collect();
+    order.sort_by(|a, b| {
+        let a_tall = bounds[*a].z.max > printer.gantry_height;
+        let b_tall = bounds[*b].z.max > printer.gantry_height;
+        a_tall
+            .cmp(&b_tall)
+            .then(bounds[*a].y.min.partial_cmp(&bounds[*b].y.min).unwrap())
+            .then(bounds[*a].x.min.partial_cmp(&bounds[*b].x.min).unwrap())
+    });
+
+    for index in order.iter().take(order.len().saturating_sub(1)) {
+        if bounds[*index].z.max > printer.gantry_height {
+            return Err(PlateError::TooTallForGantry(
+                objects[*index].name.clone(),
+                bounds[*index].z.max,
+                printer.gantry_height,
+            ));
+        }
+    }
+
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn front_to_back_then_left_to_right() {
+        let objects = vec![
+            PlateObject::test_block("back", 10.0, 120.0, 10.0),
+            PlateObject::test_block("front right", 120.0, 10.0, 10.0),
+            PlateObject::test_block("front left", 10.0, 10.0, 10.0),
+        ];
+        assert_eq!(sequence(&objects, &Printer::default()).unwrap(), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn tallest_object_goes_last() {
+        let objects = vec![
+            PlateObject::test_block("tall", 10.0, 10.0, 50.0),
+            PlateObject::test_block("short", 10.0, 120.0, 10.0),
+        ];
+        assert_eq!(sequence(&objects, &Printer::default()).unwrap(), vec![1, 0]);
+    }
+
+    #[test]
+    fn only_one_object_may_pass_the_gantry() {
+        let objects = vec![
+            PlateObject::test_block("a", 10.0, 10.0, 50.0),
+            PlateObject::test_block("b", 10.0, 120.0, 50.0),
+        ];
+        assert!(matches!(
+            sequence(&objects, &Printer::default()),
+            Err(PlateError::TooTallForGantry(ref name, height, _)) if name == "a" && height == 50.0
+        ));
+    }
+
+    #[test]
+    fn objects_inside_the_clearance_radius_collide() {
+        let objects = vec![
+            PlateObject::test_block("a", 10.0, 10.0, 10.0),
+            PlateObject::test_block("b", 40.0, 10.0, 10.0),
+        ];
+        assert!(matches!(
+            sequence(&objects, &Printer::default()),
+            Err(PlateError::ExtruderCollision(ref a, ref b)) if a == "a" && b == "b"
+        ));
+    }
+}
@@ -0,0 +1,125 @@
+use std::error;
+use std::fmt;
+
+use crate::config::*;
+use crate::mesh::*;
+use crate::plate::object::*;
+
+#[derive(Debug)]
+pub enum ValidationError {
+    OutsideBed(String, Bounds3D),
+    TooTall(String, f64, f64),
+    InExclusionZone(String, usize),
+}
+
+impl error::Error for ValidationError {}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutsideBed(name, b) => write!(
+                f,
+                "Object {} extends past the bed: x {:.2} to {:.2}, y {:.2} to {:.2}",
+                name, b.x.min, b.x.max, b.y.min, b.y.max
+            ),
+            Self::TooTall(name, height, max) => write!(
+                f,
+                "Object {} is {:.2} tall, the printer can only reach {:.2}",
+                name, height, max
+            ),
+            Self::InExclusionZone(name, zone) => {
+                write!(f, "Object {} overlaps exclusion zone {}", name, zone)
+            }
+        }
+    }
+}
+
+pub type ValidationResult<T> = Result<T, ValidationError>;
+
+pub fn validate(objects: &Vec<PlateObject>, printer: &Printer) -> ValidationResult<()> {
+    for object in objects.iter() {
+        let bounds = surface_bounds(&object.surface);
+        if bounds.z.max > printer.max_height {
+            return Err(ValidationError::TooTall(
+                object.name.clone(),
+                bounds.z.max,
+                printer.max_height,
+            ));
+        }
+
+        let footprint = object.footprint();
+        if !printer.bed_contains(&footprint) {
+            return Err(ValidationError::OutsideBed(object.name.clone(), bounds));
+        }
+
+        if let Some(zone) = printer.excluded_by(&footprint) {
+            return Err(ValidationError::InExclusionZone(object.name.clone(), zone));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn objects_on_the_bed_are_valid() {
+        let printer = Printer::default();
+        let objects = vec![PlateObject::test_block("a", 10.0, 10.0, 10.0)];
+        assert!(validate(&objects, &printer).is_ok());
+    }
+
+    #[test]
+    fn objects_past_the_bed_edge() {
+        let printer = Printer::default();
+        let objects = vec![
+            PlateObject::test_block("ok", 10.0, 10.0, 10.0),
+            PlateObject::test_block("edge", 190.0, 10.0, 10.0),
+        ];
+        assert!(matches!(
+            validate(&objects, &printer),
+            Err(ValidationError::OutsideBed(ref name, _)) if name == "edge"
+        ));
+    }
+
+    #[test]
+    fn objects_past_the_circular_bed_edge() {
+        let mut printer = Printer::default();
+        printer.bed = BedShape::Circle { diameter: 200.0 };
+        let objects = vec![PlateObject::test_block("center", 90.0, 90.0, 10.0)];
+        assert!(validate(&objects, &printer).is_ok());
+        let objects = vec![PlateObject::test_block("corner", 5.0, 5.0, 10.0)];
+        assert!(validate(&objects, &printer).is_err());
+    }
+
+    #[test]
+    fn objects_too_tall() {
+        let printer = Printer::default();
+        assert!(matches!(
+            validate(&vec![PlateObject::test_block("tall", 10.0, 10.0, 250.0)], &printer),
+            Err(ValidationError::TooTall(ref name, height, max))
+                if name == "tall" && height == 250.0 && max == printer.max_height
+        ));
+    }
+
+    #[test]
+    fn objects_in_exclusion_zones() {
+        let mut printer = Printer::default();
+        printer.exclusion_zones.push(ExclusionZone {
+            min: [0.0, 0.0],
+            max: [50.0, 50.0],
+        });
+        printer.exclusion_zones.push(ExclusionZone {
+            min: [150.0, 150.0],
+            max: [200.0, 200.0],
+        });
+        let objects = vec![PlateObject::test_block("clear", 80.0, 80.0, 10.0)];
+        assert!(validate(&objects, &printer).is_ok());
+        assert!(matches!(
+            validate(&vec![PlateObject::test_block("blocked", 160.0, 160.0, 10.0)], &printer),
+            Err(ValidationError::InExclusionZone(ref name, 1)) if name == "blocked"
+        ));
+    }
+}
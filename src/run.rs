@@ -3,13 +3,20 @@ use geo_clipper::*;
 use geo_collate::*;
 use rayon::prelude::*;
 use simple_generator::Generator;
+use std::collections::BTreeMap;
 use std::iter;
 use hedge;
 
+use crate::bridge;
+use crate::connect;
 use crate::geometry::linestring_utils::*;
+use crate::geometry::polygon_utils::*;
+use crate::lightning;
 use crate::model_file;
 use model_file::FromSurface;
 use crate::slicer;
+use crate::spiral;
+use crate::transform;
 use crate::ops;
 use crate::types::*;
 use crate::writers;
@@ -17,17 +24,144 @@ use crate::config::*;
 use crate::error::*;
 use crate::mesh::*;
 use crate::ops::*;
+use crate::pattern;
+use crate::pattern::*;
+use crate::plate;
+use crate::plate::PlateObject;
 
 pub fn run(args: Args) -> NarsilResult<()> {
+    run_with_patterns(args, &PatternRegistry::default())
+}
+
+pub fn run_with_patterns(args: Args, patterns: &PatternRegistry) -> NarsilResult<()> {
     let config = args.config()?;
-    let mut input_fh = args.input_fh()?;
+    let plate_description = args.plate()?;
 
     println!("Loading");
-    let ft = model_file::identify(&mut input_fh)?;
+    let mut objects: Vec<PlateObject> = Vec::new();
+    for description in plate_description.objects.iter() {
+        objects.extend(PlateObject::load(description)?.into_iter());
+    }
+
+    if config.auto_orient {
+        println!("Orienting");
+        for object in objects.iter_mut().filter(|o| o.auto_orient) {
+            let orientation = transform::auto_orient(&object.surface, &config);
+            println!(
+                "{}: rotated {:.1} degrees around ({:.3}, {:.3}, {:.3}): overhang area {:.2}, bed contact area {:.2}, height {:.2}",
+                object.name,
+                orientation.angle,
+                orientation.axis[0],
+                orientation.axis[1],
+                orientation.axis[2],
+                orientation.score.overhang_area,
+                orientation.score.contact_area,
+                orientation.score.height
+            );
+            object.transform(&orientation.transform);
+        }
+    }
+
+    // Shrinkage is scaled about the bed, so objects stay on it and layers
+    // keep starting at zero
+    let [shrink_x, shrink_y, shrink_z] = config.filament.shrinkage_scale();
+    let shrinkage = transform::Transform::scale(shrink_x, shrink_y, shrink_z);
+    for object in objects.iter_mut() {
+        let to_bed = transform::drop_to_bed(&object.surface);
+        object.transform(&to_bed);
+        if config.filament.compensates_shrinkage() {
+            object.transform(&shrinkage);
+        }
+    }
+
+    println!("Arranging");
+    plate::arrange(&mut objects, &config)?;
+
+    plate::validate(&objects, &config.printer)?;
 
-    let free_surface = model_file::load_triangles(&ft, &mut input_fh)?;
+    let order: Vec<usize> = if config.sequential {
+        plate::sequence(&objects, &config.printer)?
+    } else {
+        (0..objects.len()).collect()
+    };
 
-    let (surface, vertices) = model_file::unify_vertices(&free_surface);
+    let sliced = order
+        .iter()
+        .map(|i| {
+            slice_object(
+                &objects[*i].overrides.apply(&config),
+                &objects[*i].surface,
+                patterns,
+            )
+        })
+        .collect::<NarsilResult<Vec<(Vec<(f64, Vec<TaggedPath>)>, Bounds3D)>>>()?;
+
+    let bounds = sliced
+        .iter()
+        .skip(1)
+        .fold(sliced[0].1, |accum, (_, b)| accum.union(b));
+
+    let layers: Vec<Vec<TaggedPath>> = if config.sequential {
+        // Each object's full stack in turn
+        sliced
+            .into_iter()
+            .map(|(layers, _)| layers.into_iter().map(|(_, layer)| layer))
+            .flatten()
+            .collect()
+    } else {
+        // Objects keep their own regions and layer heights, layers that land
+        // on the same Z are printed together
+        let mut merged_layers = BTreeMap::<i64, Vec<TaggedPath>>::new();
+        for (layers, _) in sliced.into_iter() {
+            for (z, layer) in layers.into_iter() {
+                merged_layers
+                    .entry(config.discretized(z))
+                    .or_insert_with(Vec::new)
+                    .extend(layer.into_iter());
+            }
+        }
+        merged_layers.into_iter().map(|(_, layer)| layer).collect()
+    };
+    let num_layers = layers.len() as i64;
+
+    let metadata = vec![
+        ("filament".to_string(), config.filament.name.clone()),
+        (
+            "shrinkage_compensation".to_string(),
+            format!(
+                "x {}% y {}% z {}%",
+                config.filament.shrinkage[0],
+                config.filament.shrinkage[1],
+                config.filament.shrinkage[2]
+            ),
+        ),
+        (
+            "shrinkage_scale".to_string(),
+            format!("x {:.5} y {:.5} z {:.5}", shrink_x, shrink_y, shrink_z),
+        ),
+    ];
+
+    println!("write");
+    writers::write_html(
+        args.name(),
+        &mut args.output_fh()?,
+        layers.into_iter(),
+        num_layers,
+        &bounds,
+        config.resolution,
+        7.0,
+        &metadata,
+    )?;
+
+    Ok(())
+}
+
+fn slice_object(
+    config: &Config,
+    free_surface: &model_file::data::FreeSurface,
+    patterns: &PatternRegistry,
+) -> NarsilResult<(Vec<(f64, Vec<TaggedPath>)>, Bounds3D)> {
+    let (surface, vertices) = model_file::unify_vertices(free_surface);
 
     let connected_mesh = hedge::Mesh::from_surface(surface, vertices);
 
@@ -38,6 +172,8 @@ pub fn run(args: Args) -> NarsilResult<()> {
 
     let layer_faces = slicer::layer_faces(config.layer_height, &bounds, &sorted_faces);
 
+    let layer_z: Vec<f64> = layer_faces.iter().map(|l| l.0).collect();
+
     let outlines = layer_faces
         .par_iter()
         .map(|l| slicer::slice_layer(l.0, &connected_mesh, &l.1))
@@ -52,16 +188,50 @@ pub fn run(args: Args) -> NarsilResult<()> {
     let collated_outlines: Vec<MultiPolygon> = simplified_outlines
         .par_iter()
         .map(|l| Ok(l.collate()?))
-        .collect::<Result<Vec<MultiPolygon>, geo_collate::CollateError>>()?;
+        .collect::<Result<Vec<MultiPolygon>, geo_collate::CollateError>>()?
+        .into_par_iter()
+        .enumerate()
+        .map(compensation_op(config))
+        .collect();
 
     let outline_regions: Vec<LayerRegions> =
         collated_outlines.iter().map(|p| p.clone().into()).collect();
 
-    let shells: Vec<LayerShells> = outline_regions.par_iter().map(shells_op(&config)).collect();
+    let shells: Vec<LayerShells> = match config.wall_generator {
+        WallGenerator::Classic => outline_regions.par_iter().map(shells_op(config)).collect(),
+        WallGenerator::VariableWidth => outline_regions
+            .par_iter()
+            .map(variable_width_shells_op(config))
+            .collect(),
+    };
+
+    let shells: Vec<LayerShells> = if config.fuzzy_skin {
+        shells
+            .into_par_iter()
+            .enumerate()
+            .map(fuzzy_skin_op(config))
+            .collect()
+    } else {
+        shells
+    };
+
+    // Variable width walls already fill the gaps
+    let gap_fill: Vec<MultiLineString> =
+        if config.gap_fill && config.wall_generator == WallGenerator::Classic {
+            outline_regions.par_iter().map(gap_fill_op(config)).collect()
+        } else {
+            outline_regions.iter().map(|_| MultiLineString::default()).collect()
+        };
+
+    let thin_walls: Vec<Vec<ThinWall>> = if config.thin_walls {
+        outline_regions.par_iter().map(thin_walls_op(config)).collect()
+    } else {
+        outline_regions.iter().map(|_| Vec::new()).collect()
+    };
 
     let interiors: Vec<InteriorRegions> = outline_regions
         .par_iter()
-        .map(interiors_op(&config))
+        .map(interiors_op(config))
         .collect();
 
     let top_exposed: Vec<MultiPolygon> = ops::adjacent_gen(collated_outlines.iter())
@@ -80,16 +250,34 @@ pub fn run(args: Args) -> NarsilResult<()> {
         )
         .collect();
 
+    let skin_expansion = config.skin_expansion_distance_dsc();
+    let solid_below_area = config.solid_infill_below_area_dsc();
     let solid: Vec<SolidRegions> =
-        ops::solid_grouping_gen(&config, top_exposed.iter(), bottom_exposed.iter())
+        ops::solid_grouping_gen(config, top_exposed.iter(), bottom_exposed.iter())
             .into_iter()
             .zip(interiors.iter())
             .map(|(grouping, interior)| {
                 let interior_polys: MultiPolygon =
                     interior.0.iter().map(|i| i.poly.clone()).collect();
-                grouping
-                    .union(&MultiPolygon::default())
-                    .intersection(&interior_polys)
+                // Small islands are solid all the way through
+                let small_islands: MultiPolygon = interior
+                    .0
+                    .iter()
+                    .filter(|i| i.poly.area_f64() < solid_below_area)
+                    .map(|i| i.poly.clone())
+                    .collect();
+                // Skins reach into the interior to anchor on the infill
+                let skin = grouping.union(&MultiPolygon::default());
+                let skin = if skin_expansion > 0 {
+                    skin.offset(
+                        skin_expansion as f64,
+                        JoinType::Miter(3.0),
+                        EndType::ClosedPolygon,
+                    )
+                } else {
+                    skin
+                };
+                skin.intersection(&interior_polys).union(&small_islands)
             })
             .map(|p| p.into())
             .collect();
@@ -101,6 +289,27 @@ pub fn run(args: Args) -> NarsilResult<()> {
         .map(|p| p.into())
         .collect();
 
+    // Bridges are filled on their own, across the span
+    let bridges = bridge::detect_bridges(config, &collated_outlines, &bottom_exposed, &interiors);
+    let bridge_areas: Vec<MultiPolygon> = bridges.par_iter().map(bridge::bridge_area).collect();
+
+    let solid: Vec<SolidRegions> = solid
+        .into_iter()
+        .zip(bridge_areas.iter())
+        .map(|(s, b)| if b.0.is_empty() { s } else { s.difference(b).into() })
+        .collect();
+
+    let sparse: Vec<SparseRegions> = sparse
+        .into_iter()
+        .zip(bridge_areas.iter())
+        .map(|(s, b)| if b.0.is_empty() { s } else { s.difference(b).into() })
+        .collect();
+
+    let bridge_fill: Vec<MultiLineString> = bridges
+        .par_iter()
+        .map(|b| bridge::bridge_fill(config, b))
+        .collect();
+
     /*let mut upper = Vec::<geo::MultiPolygon<f64>>::new();
     let mut last = collated.first().unwrap().clone();
     for layer in collated.iter().skip(1) {
@@ -120,22 +329,165 @@ pub fn run(args: Args) -> NarsilResult<()> {
             accum.bound_sum(&bound)
         });
 
-    let solid_fill: Vec<MultiLineString> = solid
-        .iter()
-        .zip(ops::solid_fill_overlay_gen(&config, accum_layer_bounds).into_iter())
-        .map(|(region, pattern)| pattern.intersection(region))
-        .collect();
+    // Top skin with its own pattern is taken out of the solid regions and
+    // filled separately
+    let (skin, top_fill): (Vec<SolidRegions>, Vec<MultiLineString>) =
+        match config.top_fill_pattern {
+            TopFillPattern::Solid => (
+                solid.clone(),
+                iter::repeat(MultiLineString::default())
+                    .take(solid.len())
+                    .collect(),
+            ),
+            _ => solid
+                .par_iter()
+                .zip(top_exposed.par_iter())
+                .map(|(region, top)| {
+                    let top_area: MultiPolygon = region.intersection(top);
+                    let top_fill = top_area
+                        .0
+                        .par_iter()
+                        .filter_map(|poly| {
+                            let area: MultiPolygon = vec![poly.clone()].into();
+                            let overlay = ops::top_fill_overlay(config, poly.bounding_rect()?);
+                            Some(overlay.intersection(&area))
+                        })
+                        .reduce(MultiLineString::default, |mut a, b| {
+                            a.0.extend(b.0.into_iter());
+                            a
+                        });
+                    (SolidRegions::from(region.difference(top)), top_fill)
+                })
+                .unzip(),
+        };
 
-    let sparse_fill: Vec<MultiLineString> = sparse
-        .iter()
-        .zip(ops::sparse_fill_overlay_gen(&config, accum_layer_bounds).into_iter())
-        .map(|(region, pattern)| pattern.intersection(region))
-        .collect();
+    let solid_fill: Vec<MultiLineString> = match config.solid_fill_pattern {
+        SolidFillPattern::Concentric => skin
+            .par_iter()
+            .map(ops::concentric_fill_op(config.solid_fill_line_spacing_dsc()))
+            .collect(),
+        SolidFillPattern::Linear if !config.solid_fill_connect && !config.top_fill_monotonic => {
+            ops::fill_regions(&skin, |layer, r| ops::solid_fill_bounds(config, layer, r))
+        }
+        SolidFillPattern::Linear => skin
+            .par_iter()
+            .zip(top_exposed.par_iter())
+            .enumerate()
+            .map(|(i, (region, top))| {
+                let angle = config.solid_fill_angle(i);
+                let spacing = config.solid_fill_line_spacing_dsc();
+                let region: MultiPolygon = region.0.iter().map(|r| r.poly.clone()).collect();
+                // Lines over each polygon's own bounds, clipped to that polygon
+                let per_polygon = |area: &MultiPolygon, zigzag: bool| {
+                    let mut lines = MultiLineString::default();
+                    for poly in area.0.iter() {
+                        let bounds = match poly.bounding_rect() {
+                            Some(bounds) => bounds,
+                            None => continue,
+                        };
+                        let poly: MultiPolygon = vec![poly.clone()].into();
+                        let clipped = ops::solid_fill_bounds(config, i, bounds).intersection(&poly);
+                        if zigzag {
+                            lines.0.extend(
+                                connect::zigzag_connect(&clipped, &poly, angle, spacing).0,
+                            );
+                        } else {
+                            lines.0.extend(clipped.0);
+                        }
+                    }
+                    lines
+                };
+                let lines_in = |area: &MultiPolygon| per_polygon(area, false);
+                let connected = |area: &MultiPolygon| per_polygon(area, config.solid_fill_connect);
+
+                if config.top_fill_monotonic {
+                    let top_area = region.intersection(top);
+                    let inner_area = region.difference(top);
+                    let mut lines = connect::monotonic_order(&lines_in(&top_area), angle);
+                    lines.0.extend(connected(&inner_area).0.into_iter());
+                    lines
+                } else {
+                    connected(&region)
+                }
+            })
+            .collect(),
+    };
+
+    let octree = match config.sparse_fill_pattern {
+        FillPattern::AdaptiveCubic => Some(adaptive_octree(
+            free_surface,
+            &bounds,
+            config.sparse_fill_line_spacing_dsc() as f64 * config.resolution,
+        )),
+        _ => None,
+    };
+
+    let custom_pattern = patterns.for_config(config)?;
+
+    let sparse_fill: Vec<MultiLineString> = match config.sparse_fill_pattern {
+        _ if custom_pattern.is_some() => {
+            let layers: Vec<MultiPolygon> = sparse
+                .iter()
+                .map(|l| l.0.iter().map(|r| r.poly.clone()).collect())
+                .collect();
+            pattern::fill_regions(
+                custom_pattern.unwrap(),
+                &layers,
+                &layer_z,
+                accum_layer_bounds,
+                config,
+            )
+        }
+        FillPattern::Concentric => sparse
+            .par_iter()
+            .map(ops::concentric_fill_op(config.sparse_fill_line_spacing_dsc()))
+            .collect(),
+        FillPattern::Lightning => sparse
+            .iter()
+            .zip(lightning::lightning_fill(config, &top_exposed, &sparse).into_iter())
+            .map(|(region, pattern)| pattern.intersection(region))
+            .collect(),
+        _ => ops::fill_regions(&sparse, |layer, r| {
+            ops::sparse_fill_bounds(config, layer, layer_z[layer], octree.as_ref(), r)
+        }),
+    };
 
     let tagged_paths: Vec<Vec<TaggedPath>> = outline_regions
         .into_iter()
         .map(|l| l.into())
-        .zip(shells.into_iter().map(|l| l.into()))
+        .zip(shells.into_iter().map(|l| connect::order_layer_shells(&l, config)))
+        .map(|(a, b): (Vec<TaggedPath>, Vec<TaggedPath>)| {
+            a.into_iter().chain(b.into_iter()).collect()
+        })
+        .zip(gap_fill.into_iter().map(|l| {
+            l.0.into_iter()
+                .map(|p| TaggedPath {
+                    tag: PathTag::GapFill,
+                    path: p,
+                    widths: None,
+                    z: None,
+                    settings: None,
+                })
+                .collect()
+        }))
+        .map(|(a, b): (Vec<TaggedPath>, Vec<TaggedPath>)| {
+            a.into_iter().chain(b.into_iter()).collect()
+        })
+        .zip(thin_walls.into_iter().map(|l| {
+            l.into_iter()
+                .map(|w| TaggedPath {
+                    tag: PathTag::ThinWall,
+                    path: w.path,
+                    widths: if config.thin_wall_variable_width {
+                        Some(w.widths)
+                    } else {
+                        None
+                    },
+                    z: None,
+                    settings: None,
+                })
+                .collect()
+        }))
         .map(|(a, b): (Vec<TaggedPath>, Vec<TaggedPath>)| {
             a.into_iter().chain(b.into_iter()).collect()
         })
@@ -151,11 +503,15 @@ pub fn run(args: Args) -> NarsilResult<()> {
         .map(|(a, b): (Vec<TaggedPath>, Vec<TaggedPath>)| {
             a.into_iter().chain(b.into_iter()).collect()
         })
-        .zip(solid_fill.into_iter().map(|l| {
+        .zip(solid_fill.into_iter().zip(top_fill.into_iter()).map(|(l, t)| {
             l.0.into_iter()
+                .chain(t.0.into_iter())
                 .map(|p| TaggedPath {
                     tag: PathTag::Solid,
                     path: p,
+                    widths: None,
+                    z: None,
+                    settings: None,
                 })
                 .collect()
         }))
@@ -167,6 +523,23 @@ pub fn run(args: Args) -> NarsilResult<()> {
                 .map(|p| TaggedPath {
                     tag: PathTag::Solid,
                     path: p,
+                    widths: None,
+                    z: None,
+                    settings: None,
+                })
+                .collect()
+        }))
+        .map(|(a, b): (Vec<TaggedPath>, Vec<TaggedPath>)| {
+            a.into_iter().chain(b.into_iter()).collect()
+        })
+        .zip(bridge_fill.into_iter().map(|l| {
+            l.0.into_iter()
+                .map(|p| TaggedPath {
+                    tag: PathTag::Bridge,
+                    path: p,
+                    widths: None,
+                    z: None,
+                    settings: Some(config.bridge_path_settings()),
                 })
                 .collect()
         }))
@@ -175,16 +548,24 @@ pub fn run(args: Args) -> NarsilResult<()> {
         })
         .collect();
 
-    println!("write");
-    writers::write_html(
-        args.name(),
-        &mut args.output_fh()?,
-        tagged_paths.into_iter(),
-        (bounds.z.len() / config.layer_height) as i64 - 1,
-        &bounds,
-        config.resolution,
-        7.0,
-    )?;
+    let tagged_paths: Vec<Vec<TaggedPath>> = tagged_paths
+        .into_iter()
+        .map(|layer| connect::order_layer(layer, config))
+        .collect();
 
-    Ok(())
+    // Above the bottom layers a vase is nothing but its climbing outer wall
+    let tagged_paths: Vec<Vec<TaggedPath>> = if config.spiral_vase {
+        spiral::spiral_vase(config, &collated_outlines, &layer_z)?
+            .into_iter()
+            .zip(tagged_paths.into_iter())
+            .map(|(spiral, layer)| match spiral {
+                Some(spiral) => vec![spiral],
+                None => layer,
+            })
+            .collect()
+    } else {
+        tagged_paths
+    };
+
+    Ok((layer_z.into_iter().zip(tagged_paths).collect(), bounds))
 }
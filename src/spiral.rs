@@ -0,0 +1,221 @@
+use geo_clipper::*;
+use std::error;
+use std::fmt;
+
+use crate::config::*;
+use crate::geometry::medial_axis::path_length;
+use crate::geometry::polygon_utils::signed_ring_area;
+use crate::types::*;
+
+#[derive(Debug)]
+pub enum SpiralError {
+    MultipleIslands(usize, f64, usize),
+    SplitIsland(usize, f64, usize),
+}
+
+impl error::Error for SpiralError {}
+
+impl fmt::Display for SpiralError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MultipleIslands(layer, z, islands) => write!(
+                f,
+                "Spiral vase mode needs a single island on every layer, layer {} at z {:.3} has {}",
+                layer, z, islands
+            ),
+            Self::SplitIsland(layer, z, walls) => write!(
+                f,
+                "Spiral vase mode needs one outer wall on every layer, layer {} at z {:.3} has {}",
+                layer, z, walls
+            ),
+        }
+    }
+}
+
+pub type SpiralResult<T> = Result<T, SpiralError>;
+
+fn dist_sq(a: &Coordinate, b: &Coordinate) -> i128 {
+    let (dx, dy) = ((a.x - b.x) as i128, (a.y - b.y) as i128);
+    dx * dx + dy * dy
+}
+
+// The outer wall's center line, counter clockwise and starting at the vertex
+// nearest `start`. Narrow necks can pinch the island apart once it's inset,
+// which a single loop can't follow.
+fn contour(
+    outline: &MultiPolygon,
+    offset: f64,
+    start: Option<Coordinate>,
+    layer: usize,
+    z: f64,
+) -> SpiralResult<Option<LineString>> {
+    if outline.0.is_empty() {
+        return Ok(None);
+    }
+    let shrunk = outline.offset(offset, JoinType::Miter(3.0), EndType::ClosedPolygon);
+    if shrunk.0.len() != 1 {
+        return Err(SpiralError::SplitIsland(layer, z, shrunk.0.len()));
+    }
+
+    let exterior = shrunk.0[0].exterior();
+    let mut points = exterior.0.clone();
+    points.pop();
+    if points.len() < 3 {
+        return Ok(None);
+    }
+    if signed_ring_area(exterior) < 0.0 {
+        points.reverse();
+    }
+
+    if let Some(start) = start {
+        let nearest = (0..points.len())
+            .min_by_key(|i| dist_sq(&points[*i], &start))
+            .unwrap();
+        points.rotate_left(nearest);
+    }
+    points.push(points[0]);
+    Ok(Some(points.into()))
+}
+
+// One loop per layer above the solid bottom, following the outer wall while
+// Z climbs from the layer below to this one. Each loop starts where the last
+// one ended.
+pub fn spiral_vase(
+    config: &Config,
+    outlines: &Vec<MultiPolygon>,
+    layer_z: &Vec<f64>,
+) -> SpiralResult<Vec<Option<TaggedPath>>> {
+    let offset = -(config.nozzle_diameter_dsc() / 2) as f64;
+    let num_bottom_layers = config.num_bottom_layers();
+
+    for (layer, outline) in outlines.iter().enumerate().skip(num_bottom_layers) {
+        if outline.0.len() > 1 {
+            return Err(SpiralError::MultipleIslands(layer, layer_z[layer], outline.0.len()));
+        }
+    }
+
+    let mut end: Option<Coordinate> = None;
+    let mut loops = Vec::with_capacity(outlines.len());
+    for (layer, outline) in outlines.iter().enumerate() {
+        if layer < num_bottom_layers {
+            loops.push(None);
+            continue;
+        }
+
+        let path = match contour(outline, offset, end, layer, layer_z[layer])? {
+            Some(path) => path,
+            None => {
+                loops.push(None);
+                continue;
+            }
+        };
+
+        let total = path_length(&path);
+        let below = if layer > 0 {
+            layer_z[layer - 1]
+        } else {
+            layer_z[layer] - config.layer_height
+        };
+        let rise = layer_z[layer] - below;
+
+        let mut along = 0.0;
+        let mut z = vec![below];
+        for w in path.0.windows(2) {
+            along += path_length(&w.to_vec().into());
+            z.push(below + rise * along / total);
+        }
+
+        end = path.0.last().cloned();
+        loops.push(Some(TaggedPath {
+            tag: PathTag::Shell,
+            path,
+            widths: None,
+            z: Some(z),
+            settings: None,
+        }));
+    }
+
+    Ok(loops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(config: &Config, x: f64, size: f64) -> Polygon {
+        let dsc = |mm: f64| config.discretized(mm);
+        let (min, max) = (dsc(x), dsc(x + size));
+        Polygon::new(
+            vec![(min, 0), (max, 0), (max, dsc(size)), (min, dsc(size)), (min, 0)].into(),
+            vec![],
+        )
+    }
+
+    fn layer_z(config: &Config, num_layers: usize) -> Vec<f64> {
+        (1..=num_layers).map(|l| l as f64 * config.layer_height).collect()
+    }
+
+    #[test]
+    fn spiral_climbs_one_layer_per_loop() {
+        let config = Config::default_test();
+        let num_layers = config.num_bottom_layers() + 3;
+        let outlines = vec![MultiPolygon::from(vec![square(&config, 0.0, 20.0)]); num_layers];
+        let layer_z = layer_z(&config, num_layers);
+
+        let loops = spiral_vase(&config, &outlines, &layer_z).unwrap();
+        assert!(loops[..config.num_bottom_layers()].iter().all(|l| l.is_none()));
+        for layer in config.num_bottom_layers()..num_layers {
+            let path = loops[layer].as_ref().unwrap();
+            let z = path.z.as_ref().unwrap();
+            assert_eq!(z.len(), path.path.0.len());
+            assert!((z[0] - layer_z[layer - 1]).abs() < 1e-9);
+            assert!((z[z.len() - 1] - layer_z[layer]).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn spiral_rejects_multiple_islands() {
+        let config = Config::default_test();
+        let num_layers = config.num_bottom_layers() + 2;
+        let mut outlines = vec![MultiPolygon::from(vec![square(&config, 0.0, 20.0)]); num_layers];
+        outlines[num_layers - 1].0.push(square(&config, 30.0, 20.0));
+
+        assert!(matches!(
+            spiral_vase(&config, &outlines, &layer_z(&config, num_layers)),
+            Err(SpiralError::MultipleIslands(layer, _, 2)) if layer == num_layers - 1
+        ));
+    }
+
+    #[test]
+    fn spiral_rejects_islands_pinched_apart() {
+        let config = Config::default_test();
+        let num_layers = config.num_bottom_layers() + 1;
+        let dsc = |mm: f64| config.discretized(mm);
+        // Two squares joined by a neck narrower than the nozzle
+        let dumbbell = Polygon::new(
+            vec![
+                (0, 0),
+                (dsc(10.0), 0),
+                (dsc(10.0), dsc(4.85)),
+                (dsc(20.0), dsc(4.85)),
+                (dsc(20.0), 0),
+                (dsc(30.0), 0),
+                (dsc(30.0), dsc(10.0)),
+                (dsc(20.0), dsc(10.0)),
+                (dsc(20.0), dsc(5.15)),
+                (dsc(10.0), dsc(5.15)),
+                (dsc(10.0), dsc(10.0)),
+                (0, dsc(10.0)),
+                (0, 0),
+            ]
+            .into(),
+            vec![],
+        );
+        let outlines = vec![MultiPolygon::from(vec![dumbbell]); num_layers];
+
+        assert!(matches!(
+            spiral_vase(&config, &outlines, &layer_z(&config, num_layers)),
+            Err(SpiralError::SplitIsland(_, _, 2))
+        ));
+    }
+}
@@ -0,0 +1,5 @@
+pub mod orient;
+pub mod transform;
+
+pub use self::orient::*;
+pub use self::transform::*;
@@ -0,0 +1,227 @@
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::f64;
+
+use crate::config::*;
+use crate::model_file::data::*;
+use crate::transform::transform::*;
+
+const DOWN: Vertex = [0.0, 0.0, -1.0];
+const NUM_FACE_CANDIDATES: usize = 32;
+const NUM_SPHERE_SAMPLES: usize = 128;
+const FLAT_TOLERANCE: f64 = 0.9998; // cos(~1 degree)
+const NORMAL_BUCKETS: f64 = 60.0; // per unit, ~1 degree wide
+const CONTACT_EPSILON: f64 = 0.01;
+
+const OVERHANG_WEIGHT: f64 = 1.0;
+const CONTACT_WEIGHT: f64 = 0.5;
+const HEIGHT_WEIGHT: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy)]
+pub struct OrientationScore {
+    pub overhang_area: f64,
+    pub contact_area: f64,
+    pub height: f64,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Orientation {
+    pub transform: Transform,
+    pub axis: Vertex,
+    pub angle: f64,
+    pub score: OrientationScore,
+}
+
+struct OrientFace {
+    normal: Vertex,
+    area: f64,
+    vertices: FreeTriangle,
+}
+
+fn orient_faces(surface: &FreeSurface) -> Vec<OrientFace> {
+    surface
+        .iter()
+        .filter_map(|triangle| {
+            let a = [
+                triangle[1][0] - triangle[0][0],
+                triangle[1][1] - triangle[0][1],
+                triangle[1][2] - triangle[0][2],
+            ];
+            let b = [
+                triangle[2][0] - triangle[0][0],
+                triangle[2][1] - triangle[0][1],
+                triangle[2][2] - triangle[0][2],
+            ];
+            let n = cross(&a, &b);
+            let double_area = length(&n);
+            if double_area == 0.0 {
+                return None;
+            }
+            Some(OrientFace {
+                normal: normalize(&n),
+                area: double_area / 2.0,
+                vertices: *triangle,
+            })
+        })
+        .collect()
+}
+
+// Candidate directions are the directions that end up pointing at the bed
+fn face_candidates(faces: &Vec<OrientFace>) -> Vec<Vertex> {
+    let mut buckets: BTreeMap<(i64, i64, i64), (Vertex, f64)> = BTreeMap::new();
+    for face in faces.iter() {
+        let n = face.normal;
+        let key = (
+            (n[0] * NORMAL_BUCKETS).round() as i64,
+            (n[1] * NORMAL_BUCKETS).round() as i64,
+            (n[2] * NORMAL_BUCKETS).round() as i64,
+        );
+        let bucket = buckets.entry(key).or_insert(([0.0; 3], 0.0));
+        for (sum, component) in bucket.0.iter_mut().zip(n.iter()) {
+            *sum += component * face.area;
+        }
+        bucket.1 += face.area;
+    }
+
+    let mut clusters: Vec<(Vertex, f64)> = buckets
+        .into_values()
+        .map(|(sum, area)| (normalize(&sum), area))
+        .collect();
+    clusters.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    clusters
+        .into_iter()
+        .take(NUM_FACE_CANDIDATES)
+        .map(|(normal, _)| normal)
+        .collect()
+}
+
+// Fibonacci lattice, roughly even coverage of the unit sphere
+fn sphere_candidates(num_samples: usize) -> Vec<Vertex> {
+    let golden_angle = f64::consts::PI * (3.0 - 5.0_f64.sqrt());
+    (0..num_samples)
+        .map(|i| {
+            let z = 1.0 - 2.0 * (i as f64 + 0.5) / num_samples as f64;
+            let r = (1.0 - z * z).sqrt();
+            let theta = golden_angle * i as f64;
+            [r * theta.cos(), r * theta.sin(), z]
+        })
+        .collect()
+}
+
+fn score_orientation(
+    faces: &Vec<OrientFace>,
+    transform: &Transform,
+    overhang_angle: f64,
+    total_area: f64,
+    size: f64,
+) -> OrientationScore {
+    let overhang_threshold = overhang_angle.to_radians().sin();
+
+    let mut min_z = f64::INFINITY;
+    let mut max_z = f64::NEG_INFINITY;
+    for face in faces.iter() {
+        for vertex in face.vertices.iter() {
+            let z = transform.apply(vertex)[2];
+            min_z = min_z.min(z);
+            max_z = max_z.max(z);
+        }
+    }
+
+    let mut overhang_area = 0.0;
+    let mut contact_area = 0.0;
+    for face in faces.iter() {
+        let downward = -transform.apply_direction(&face.normal)[2];
+        if downward <= overhang_threshold {
+            continue;
+        }
+
+        let on_bed = face
+            .vertices
+            .iter()
+            .all(|vertex| transform.apply(vertex)[2] - min_z < CONTACT_EPSILON);
+
+        if on_bed && downward > FLAT_TOLERANCE {
+            contact_area += face.area;
+        } else {
+            overhang_area += face.area;
+        }
+    }
+
+    let height = max_z - min_z;
+    let score = OVERHANG_WEIGHT * overhang_area / total_area
+        - CONTACT_WEIGHT * contact_area / total_area
+        + HEIGHT_WEIGHT * height / size;
+
+    OrientationScore {
+        overhang_area,
+        contact_area,
+        height,
+        score,
+    }
+}
+
+pub fn auto_orient(surface: &FreeSurface, config: &Config) -> Orientation {
+    let faces = orient_faces(surface);
+    let total_area: f64 = faces.iter().map(|face| face.area).sum();
+
+    let mut min = [f64::INFINITY; 3];
+    let mut max = [f64::NEG_INFINITY; 3];
+    for vertex in surface.iter().flatten() {
+        for i in 0..3 {
+            min[i] = min[i].min(vertex[i]);
+            max[i] = max[i].max(vertex[i]);
+        }
+    }
+    let size = length(&[max[0] - min[0], max[1] - min[1], max[2] - min[2]]);
+
+    let candidates: Vec<Vertex> = std::iter::once(DOWN)
+        .chain(face_candidates(&faces).into_iter())
+        .chain(sphere_candidates(NUM_SPHERE_SAMPLES).into_iter())
+        .collect();
+
+    candidates
+        .par_iter()
+        .map(|direction| {
+            let (transform, axis, angle) = Transform::rotation_between(direction, &DOWN);
+            let score =
+                score_orientation(&faces, &transform, config.overhang_angle, total_area, size);
+            Orientation {
+                transform,
+                axis,
+                angle,
+                score,
+            }
+        })
+        .reduce_with(|a, b| if b.score.score < a.score.score { b } else { a })
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plate::PlateObject;
+
+    #[test]
+    fn plate_on_its_edge_is_laid_flat() {
+        let config = Config::default_test();
+        let plate = PlateObject::test_box("plate", [0.0; 3], [40.0, 2.0, 20.0]).surface;
+        let faces = orient_faces(&plate);
+        let total_area: f64 = faces.iter().map(|face| face.area).sum();
+        let size = length(&[40.0, 2.0, 20.0]);
+        let standing = score_orientation(
+            &faces,
+            &Transform::identity(),
+            config.overhang_angle,
+            total_area,
+            size,
+        );
+
+        let best = auto_orient(&plate, &config);
+        assert!((best.score.height - 2.0).abs() < 1e-6);
+        assert!(best.score.overhang_area <= standing.overhang_area + 1e-6);
+        assert!((best.score.contact_area - 800.0).abs() < 1e-6);
+        assert!(best.score.contact_area > standing.contact_area);
+        assert!(best.score.score < standing.score);
+    }
+}
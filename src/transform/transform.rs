@@ -0,0 +1,199 @@
+use crate::model_file::data::*;
+use std::f64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub matrix: [[f64; 4]; 3],
+}
+
+pub fn dot(a: &Vertex, b: &Vertex) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: &Vertex, b: &Vertex) -> Vertex {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub fn length(a: &Vertex) -> f64 {
+    dot(a, a).sqrt()
+}
+
+pub fn normalize(a: &Vertex) -> Vertex {
+    let len = length(a);
+    [a[0] / len, a[1] / len, a[2] / len]
+}
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform {
+            matrix: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+            ],
+        }
+    }
+
+    pub fn translation(x: f64, y: f64, z: f64) -> Transform {
+        Transform {
+            matrix: [
+                [1.0, 0.0, 0.0, x],
+                [0.0, 1.0, 0.0, y],
+                [0.0, 0.0, 1.0, z],
+            ],
+        }
+    }
+
+    pub fn scale(x: f64, y: f64, z: f64) -> Transform {
+        Transform {
+            matrix: [
+                [x, 0.0, 0.0, 0.0],
+                [0.0, y, 0.0, 0.0],
+                [0.0, 0.0, z, 0.0],
+            ],
+        }
+    }
+
+    pub fn axis_angle(axis: &Vertex, angle: f64) -> Transform {
+        let [x, y, z] = normalize(axis);
+        let (s, c) = angle.to_radians().sin_cos();
+        let t = 1.0 - c;
+        Transform {
+            matrix: [
+                [t * x * x + c, t * x * y - s * z, t * x * z + s * y, 0.0],
+                [t * x * y + s * z, t * y * y + c, t * y * z - s * x, 0.0],
+                [t * x * z - s * y, t * y * z + s * x, t * z * z + c, 0.0],
+            ],
+        }
+    }
+
+    // Rotation taking direction `from` onto direction `to`, returned with
+    // the axis and angle (in degrees) it rotates by
+    pub fn rotation_between(from: &Vertex, to: &Vertex) -> (Transform, Vertex, f64) {
+        let from = normalize(from);
+        let to = normalize(to);
+        let cos_angle = dot(&from, &to).max(-1.0).min(1.0);
+        let axis = cross(&from, &to);
+
+        if length(&axis) < 1e-9 {
+            if cos_angle > 0.0 {
+                return (Transform::identity(), [0.0, 0.0, 1.0], 0.0);
+            }
+            // Antiparallel, any perpendicular axis will do
+            let helper = if from[0].abs() < 0.9 {
+                [1.0, 0.0, 0.0]
+            } else {
+                [0.0, 1.0, 0.0]
+            };
+            let axis = normalize(&cross(&from, &helper));
+            return (Transform::axis_angle(&axis, 180.0), axis, 180.0);
+        }
+
+        let axis = normalize(&axis);
+        let angle = cos_angle.acos().to_degrees();
+        (Transform::axis_angle(&axis, angle), axis, angle)
+    }
+
+    // Compose so that `self` is applied first, then `other`
+    pub fn then(&self, other: &Transform) -> Transform {
+        let a = &other.matrix;
+        let b = &self.matrix;
+        let mut matrix = [[0.0; 4]; 3];
+        for (out, a_row) in matrix.iter_mut().zip(a.iter()) {
+            for (col, value) in out.iter_mut().enumerate() {
+                *value = a_row[0] * b[0][col] + a_row[1] * b[1][col] + a_row[2] * b[2][col];
+            }
+            out[3] += a_row[3];
+        }
+        Transform { matrix }
+    }
+
+    pub fn apply(&self, v: &Vertex) -> Vertex {
+        let m = &self.matrix;
+        [
+            m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2] + m[0][3],
+            m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2] + m[1][3],
+            m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2] + m[2][3],
+        ]
+    }
+
+    pub fn apply_direction(&self, v: &Vertex) -> Vertex {
+        let m = &self.matrix;
+        [
+            m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+            m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+            m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+        ]
+    }
+}
+
+pub fn transform_surface(surface: &FreeSurface, transform: &Transform) -> FreeSurface {
+    surface
+        .iter()
+        .map(|triangle| {
+            [
+                transform.apply(&triangle[0]),
+                transform.apply(&triangle[1]),
+                transform.apply(&triangle[2]),
+            ]
+        })
+        .collect()
+}
+
+pub fn surface_min_z(surface: &FreeSurface) -> f64 {
+    surface
+        .iter()
+        .map(|triangle| triangle.iter().map(|v| v[2]))
+        .flatten()
+        .fold(f64::INFINITY, f64::min)
+}
+
+// Translation that sets the lowest point of the surface on the bed
+pub fn drop_to_bed(surface: &FreeSurface) -> Transform {
+    Transform::translation(0.0, 0.0, -surface_min_z(surface))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: Vertex, b: Vertex) {
+        let diff = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+        assert!(length(&diff) < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn then_applies_self_first() {
+        let scale = Transform::scale(2.0, 3.0, 4.0);
+        let translation = Transform::translation(1.0, 1.0, 1.0);
+        assert_near(scale.then(&translation).apply(&[1.0, 1.0, 1.0]), [3.0, 4.0, 5.0]);
+        assert_near(translation.then(&scale).apply(&[1.0, 1.0, 1.0]), [4.0, 6.0, 8.0]);
+    }
+
+    #[test]
+    fn rotation_between_maps_from_onto_to() {
+        let (rotation, axis, angle) =
+            Transform::rotation_between(&[1.0, 0.0, 0.0], &[0.0, 2.0, 0.0]);
+        assert_near(rotation.apply_direction(&[1.0, 0.0, 0.0]), [0.0, 1.0, 0.0]);
+        assert_near(axis, [0.0, 0.0, 1.0]);
+        assert!((angle - 90.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rotation_between_parallel_and_antiparallel() {
+        let up = [0.0, 0.0, 1.0];
+        let down = [0.0, 0.0, -1.0];
+        let (rotation, _, angle) = Transform::rotation_between(&up, &up);
+        assert_eq!(rotation, Transform::identity());
+        assert_eq!(angle, 0.0);
+
+        let (rotation, axis, angle) = Transform::rotation_between(&up, &down);
+        assert_near(rotation.apply_direction(&up), down);
+        assert!(dot(&axis, &up).abs() < 1e-9);
+        assert_eq!(angle, 180.0);
+    }
+}
@@ -1,6 +1,7 @@
 use geo;
 
 use crate::id_factory::*;
+use serde::{Deserialize, Serialize};
 use geo_clipper::{ClosedPoly, OwnedPolygon, ToOwnedPolygonInt};
 use std::cmp;
 use types::traits::IntoLineStrings;
@@ -20,6 +21,9 @@ pub enum PathTag {
     Interior,
     Solid,
     Sparse,
+    Bridge,
+    ThinWall,
+    GapFill,
     Unknown,
 }
 
@@ -54,6 +58,12 @@ impl<Tag: RegionTag> TaggedRegions<Tag> {
     }
 }
 
+impl<Tag: RegionTag> Clone for TaggedRegions<Tag> {
+    fn clone(&self) -> Self {
+        Self::new(self.0.clone())
+    }
+}
+
 pub type LayerRegions = TaggedRegions<OutlineRegionTag>;
 pub type InteriorRegions = TaggedRegions<InteriorRegionTag>;
 pub type SolidRegions = TaggedRegions<SolidRegionTag>;
@@ -89,25 +99,35 @@ impl<Tag: RegionTag> From<MultiPolygon> for TaggedRegions<Tag> {
 
 pub struct Shells {
     pub shells: Vec<MultiLineString>,
+    // Which loops of each rank go around a hole rather than the outside
+    pub holes: Vec<Vec<bool>>,
+    // Width at each vertex of each loop of each rank, empty when every wall
+    // is the nozzle width
+    pub widths: Vec<Vec<Vec<i64>>>,
     pub region_id: u64,
 }
 
 pub struct SingleShell {
     pub shell: LineString,
+    pub widths: Option<Vec<i64>>,
     pub region_id: u64,
     pub rank: usize,
+    pub hole: bool,
 }
 
 impl Shells {
-    fn to_single_shells(&self) -> Vec<SingleShell> {
+    pub fn to_single_shells(&self) -> Vec<SingleShell> {
         self.shells.iter()
+            .zip(self.holes.iter())
             .enumerate()
-            .map(|(rank, shells)|
-                 shells.iter().map(|shell|
+            .map(|(rank, (shells, holes))|
+                 shells.iter().zip(holes.iter()).enumerate().map(|(i, (shell, hole))|
                             SingleShell {
                                 shell: shell.clone(),
+                                widths: self.widths.get(rank).map(|w| w[i].clone()),
                                 region_id: self.region_id,
-                                rank: rank
+                                rank: rank,
+                                hole: *hole
                             }).collect::<Vec<SingleShell>>())
             .flatten()
             .collect()
@@ -140,9 +160,30 @@ impl<Tag: RegionTag> ToOwnedPolygonInt for TaggedRegions<Tag> {
 
 impl<Tag: RegionTag> ClosedPoly for TaggedRegions<Tag> {}
 
+// Single extrusion down the middle of a feature too narrow for shells, with
+// the feature's width at each vertex
+pub struct ThinWall {
+    pub path: LineString,
+    pub widths: Vec<i64>,
+}
+
+// Print settings for paths that don't print at the defaults
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PathSettings {
+    pub speed: f64,
+    // Ratio of the usual extrusion
+    pub flow: f64,
+    pub fan_speed: f64,
+}
+
 pub struct TaggedPath {
     pub tag: PathTag,
     pub path: LineString,
+    // Extrusion width at each vertex, when it isn't the nozzle width
+    pub widths: Option<Vec<i64>>,
+    // Z at each vertex, for paths that climb rather than stay on the layer
+    pub z: Option<Vec<f64>>,
+    pub settings: Option<PathSettings>,
 }
 
 impl From<Region> for Vec<TaggedPath> {
@@ -152,6 +193,9 @@ impl From<Region> for Vec<TaggedPath> {
             .map(|path| TaggedPath {
                 tag: PathTag::Region,
                 path,
+                widths: None,
+                z: None,
+                settings: None,
             })
             .collect()
     }
@@ -166,6 +210,9 @@ impl<Tag: RegionTag> From<TaggedRegions<Tag>> for Vec<TaggedPath> {
                 region.into_line_strings().map(|path| TaggedPath {
                     tag: Tag::PATHTAG,
                     path,
+                    widths: None,
+                    z: None,
+                    settings: None,
                 })
             })
             .flatten()
@@ -182,15 +229,15 @@ impl From<LayerRegions> for InteriorRegions {
 impl From<Shells> for Vec<TaggedPath> {
     fn from(shells: Shells) -> Self {
         shells
-            .shells
+            .to_single_shells()
             .into_iter()
-            .map(|paths| {
-                paths.into_iter().map(|path| TaggedPath {
-                    tag: PathTag::Shell,
-                    path,
-                })
+            .map(|single| TaggedPath {
+                tag: PathTag::Shell,
+                path: single.shell,
+                widths: single.widths,
+                z: None,
+                settings: None,
             })
-            .flatten()
             .collect()
     }
 }
@@ -274,8 +321,97 @@ impl BoundOps for Rect {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
 pub enum FillPattern {
     Linear,
+    Grid,
+    Triangles,
+    Gyroid,
+    Honeycomb,
+    Cubic,
+    AdaptiveCubic,
+    Lightning,
+    Concentric,
+}
+
+impl FillPattern {
+    // Number of line sets laid down per layer, each at the full line spacing
+    pub fn line_sets(&self) -> u32 {
+        match self {
+            Self::Linear
+            | Self::Gyroid
+            | Self::Honeycomb
+            | Self::Lightning
+            | Self::Concentric => 1,
+            Self::Grid => 2,
+            Self::Triangles | Self::Cubic | Self::AdaptiveCubic => 3,
+        }
+    }
+}
+
+impl Default for FillPattern {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WallOrder {
+    OuterInner,
+    InnerOuter,
+    InnerOuterInner,
+}
+
+impl Default for WallOrder {
+    fn default() -> Self {
+        Self::OuterInner
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WallGenerator {
+    Classic,
+    // Walls widened and moved apart to fill the region, with a width at
+    // every vertex
+    VariableWidth,
+}
+
+impl Default for WallGenerator {
+    fn default() -> Self {
+        Self::Classic
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SolidFillPattern {
+    Linear,
+    Concentric,
+}
+
+impl Default for SolidFillPattern {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+// Pattern for the visible skin of top-exposed layers. `Solid` uses the
+// configured solid fill pattern.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TopFillPattern {
+    Solid,
+    HilbertCurve,
+    ArchimedeanChords,
+}
+
+impl Default for TopFillPattern {
+    fn default() -> Self {
+        Self::Solid
+    }
 }
 
 pub struct PathGroup<Tag: RegionTag> {
@@ -9,6 +9,14 @@ use svg::Document;
 use crate::mesh::Bounds3D;
 use types::*;
 
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
 pub fn write_html(
     name: String,
     fh: &mut File,
@@ -17,7 +25,19 @@ pub fn write_html(
     bounds: &Bounds3D,
     resolution: f64,
     factor: f64,
+    metadata: &[(String, String)],
 ) -> Result<(), std::io::Error> {
+    let meta_tags: String = metadata
+        .iter()
+        .map(|(name, content)| {
+            format!(
+                "<meta name=\"narsil:{}\" content=\"{}\">\n",
+                escape_html(name),
+                escape_html(content)
+            )
+        })
+        .collect();
+
     let mut document = Document::new()
         .set(
             "viewbox",
@@ -35,36 +55,74 @@ pub fn write_html(
             .set("display", "none");
 
         for path in slice.into_iter() {
-            let poly = path.path;
-
-            let mut data = path::Data::new().move_to((
-                (poly.0[0].x as f64 * resolution - bounds.x.min) * factor,
-                (poly.0[0].y as f64 * resolution - bounds.y.min) * factor,
-            ));
-
-            for point in poly.0.iter().skip(1) {
-                data = data.line_to((
-                    (point.x as f64 * resolution - bounds.x.min) * factor,
-                    (point.y as f64 * resolution - bounds.y.min) * factor,
-                ));
-            }
-
-            data = data.close();
-
             let color = match path.tag {
                 PathTag::Region => "black",
                 PathTag::Shell => "red",
                 PathTag::Interior => "yellow",
                 PathTag::Solid => "green",
                 PathTag::Sparse => "blue",
+                PathTag::Bridge => "orange",
+                PathTag::ThinWall => "magenta",
+                PathTag::GapFill => "cyan",
                 PathTag::Unknown => "grey",
             };
 
-            let path = svgPath::new()
-                .set("fill", "none")
-                .set("stroke", color)
-                .set("stroke-width", 0.2)
-                .set("d", data);
+            let poly = path.path;
+            let scaled = |point: &Coordinate| {
+                (
+                    (point.x as f64 * resolution - bounds.x.min) * factor,
+                    (point.y as f64 * resolution - bounds.y.min) * factor,
+                )
+            };
+
+            let settings = path.settings;
+            let with_settings = |svg_path: svgPath| match settings {
+                Some(settings) => svg_path
+                    .set("data-speed", settings.speed)
+                    .set("data-flow", settings.flow)
+                    .set("data-fan-speed", settings.fan_speed),
+                None => svg_path,
+            };
+
+            // Paths with their own widths are drawn a segment at a time, each
+            // as wide as the extrusion
+            if let Some(widths) = path.widths {
+                for (segment, w) in poly.0.windows(2).zip(widths.windows(2)) {
+                    let data = path::Data::new()
+                        .move_to(scaled(&segment[0]))
+                        .line_to(scaled(&segment[1]));
+                    let stroke_width = (w[0] + w[1]) as f64 / 2.0 * resolution * factor;
+                    group = group.add(with_settings(
+                        svgPath::new()
+                            .set("fill", "none")
+                            .set("stroke", color)
+                            .set("stroke-width", stroke_width)
+                            .set("stroke-opacity", 0.5)
+                            .set("stroke-linecap", "round")
+                            .set("d", data),
+                    ));
+                }
+                continue;
+            }
+
+            let mut data = path::Data::new().move_to(scaled(&poly.0[0]));
+
+            for point in poly.0.iter().skip(1) {
+                data = data.line_to(scaled(point));
+            }
+
+            // Only loops are closed, open fill lines and climbing spirals are not
+            if poly.0.len() > 2 && poly.0.first() == poly.0.last() {
+                data = data.close();
+            }
+
+            let path = with_settings(
+                svgPath::new()
+                    .set("fill", "none")
+                    .set("stroke", color)
+                    .set("stroke-width", 0.2)
+                    .set("d", data),
+            );
 
             group = group.add(path);
         }
@@ -76,7 +134,7 @@ pub fn write_html(
             r#"
 <!DOCTYPE html><html><head><title>{}</title>
 <meta name="viewport" content="width=device-width, initial-scale=1">
-<style>
+{}<style>
 .slidecontainer {{
     width: 100%;
 }}
@@ -118,7 +176,8 @@ pub fn write_html(
     <p>Value: <span id="layerId"></span></p>
 </div>
 "#,
-            name,
+            escape_html(&name),
+            meta_tags,
             num_slices - 1
         )
         .as_bytes(),
@@ -158,3 +217,16 @@ slider.oninput = function() {{
     )?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meta_content_is_escaped() {
+        assert_eq!(
+            escape_html(r#"PLA "red" <b> & 'co'"#),
+            "PLA &quot;red&quot; &lt;b&gt; &amp; &#39;co&#39;"
+        );
+    }
+}